@@ -1,16 +1,18 @@
 //! Development tools for the game. This plugin is only enabled in dev builds.
 #![cfg(feature = "dev")]
 
-use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use bevy::prelude::*;
+
+use crate::input::{GameAction, action_just_pressed};
 
 pub(crate) fn plugin(app: &mut App) {
-    let toggle_system = toggle_debug_ui.run_if(input_just_pressed(TOGGLE_KEY));
+    let toggle_system = toggle_debug_ui.run_if(action_just_pressed(TOGGLE_ACTION));
 
     // Toggle the debug overlay for UI.
     app.add_systems(Update, toggle_system);
 }
 
-const TOGGLE_KEY: KeyCode = KeyCode::Numpad1;
+const TOGGLE_ACTION: GameAction = GameAction::ToggleUiDebug;
 
 fn toggle_debug_ui(mut options: ResMut<UiDebugOptions>) {
     options.toggle();
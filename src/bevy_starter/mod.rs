@@ -8,11 +8,14 @@ pub mod fonts;
 pub mod game;
 pub mod input;
 pub mod physics;
+pub mod rng;
+pub mod ron_asset;
 pub mod utils;
 
 pub fn add_all_plugins(app: &mut App) {
     app.add_plugins(asset_tracking::plugin);
     app.add_plugins(default::plugin);
+    app.add_plugins(rng::plugin);
     app.add_plugins(fonts::plugin);
     app.add_plugins(physics::plugin);
     app.add_plugins(input::plugin);
@@ -22,6 +25,7 @@ pub fn add_all_plugins(app: &mut App) {
 }
 
 pub mod prelude {
+    pub use super::rng::{GameRng, RngStream};
     pub use super::utils::*;
 }
 
@@ -0,0 +1,177 @@
+//! One seed for all randomness in the game, so a run can be reproduced.
+//!
+//! The [`GameRng`] splits its master seed into an independent stream per [`RngStream`], so one
+//! system drawing more numbers doesn't change what the others get. The master seed is random,
+//! unless it is given with `--seed <seed>` or the `SPRAYPAINT_SEED` environment variable. Either
+//! way it is logged at startup, to replay a run with the same seed.
+
+use bevy::{platform::collections::HashMap, prelude::*};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+/// The environment variable that overrides the master seed.
+pub const SEED_VARIABLE: &str = "SPRAYPAINT_SEED";
+/// The command line argument that overrides the master seed, before [`SEED_VARIABLE`].
+pub const SEED_ARGUMENT: &str = "--seed";
+
+pub(super) fn plugin(app: &mut App) {
+    let rng = GameRng::from_env();
+    info!("Random seed: {}", rng.seed());
+    app.insert_resource(rng);
+}
+
+/// Who draws from a stream of the [`GameRng`]. Each one gets numbers independent of the others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RngStream {
+    LevelGeneration,
+    SprayScatter,
+    Ai,
+}
+
+impl RngStream {
+    /// The ChaCha stream this draws from.
+    fn id(self) -> u64 {
+        match self {
+            RngStream::LevelGeneration => 0,
+            RngStream::SprayScatter => 1,
+            RngStream::Ai => 2,
+        }
+    }
+}
+
+/// The random number generator everything random in the game draws from.
+///
+/// The same master seed always gives the same numbers in each [`RngStream`], on every platform.
+/// Defaults to a master seed of 0.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct GameRng {
+    seed: u64,
+    streams: HashMap<RngStream, ChaCha8Rng>,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            streams: HashMap::default(),
+        }
+    }
+
+    /// Seeded from the command line or the environment, see the [module docs](self), or a
+    /// random seed otherwise.
+    pub fn from_env() -> Self {
+        let argument = std::env::args()
+            .skip_while(|argument| argument != SEED_ARGUMENT)
+            .nth(1);
+        let seed = argument
+            .or_else(|| std::env::var(SEED_VARIABLE).ok())
+            .and_then(|seed| match parse_seed(&seed) {
+                Some(seed) => Some(seed),
+                None => {
+                    warn!("Ignoring seed {seed:?}, it should be a number");
+                    None
+                }
+            })
+            .unwrap_or_else(rand::random);
+        Self::new(seed)
+    }
+
+    /// The master seed all streams are made from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Starts all streams over from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// The generator of `stream`, to draw numbers from.
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        let seed = self.seed;
+        self.streams.entry(stream).or_insert_with(|| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(stream.id());
+            rng
+        })
+    }
+
+    /// Where every stream is at, to [`GameRng::restore`] later.
+    pub fn save(&self) -> GameRngState {
+        let mut positions: Vec<_> = self
+            .streams
+            .iter()
+            .map(|(stream, rng)| (*stream, rng.get_word_pos()))
+            .collect();
+        // Sorted, so the same state always saves the same.
+        positions.sort_by_key(|(stream, _)| stream.id());
+        GameRngState {
+            seed: self.seed,
+            positions,
+        }
+    }
+
+    /// Puts every stream back where it was when `state` was saved.
+    pub fn restore(&mut self, state: &GameRngState) {
+        self.reseed(state.seed);
+        for &(stream, position) in &state.positions {
+            self.stream(stream).set_word_pos(position);
+        }
+    }
+}
+
+/// A saved [`GameRng`]: its master seed, and how many words each stream has used.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRngState {
+    pub seed: u64,
+    /// The word position of each stream, which can go past `u64::MAX`.
+    pub positions: Vec<(RngStream, u128)>,
+}
+
+/// Parses a decimal or `0x` hexadecimal seed.
+fn parse_seed(seed: &str) -> Option<u64> {
+    let seed = seed.trim();
+    match seed.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => seed.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+
+    fn draw(rng: &mut GameRng, stream: RngStream) -> Vec<u64> {
+        (0..8).map(|_| rng.stream(stream).random()).collect()
+    }
+
+    #[test]
+    fn restoring_a_save_repeats_the_next_draws() {
+        let mut rng = GameRng::new(42);
+        draw(&mut rng, RngStream::LevelGeneration);
+        draw(&mut rng, RngStream::SprayScatter);
+        let state = rng.save();
+        let level = draw(&mut rng, RngStream::LevelGeneration);
+        let spray = draw(&mut rng, RngStream::SprayScatter);
+
+        let mut restored = GameRng::default();
+        restored.restore(&state);
+        assert_eq!(restored.save(), state);
+        assert_eq!(draw(&mut restored, RngStream::LevelGeneration), level);
+        assert_eq!(draw(&mut restored, RngStream::SprayScatter), spray);
+    }
+
+    #[test]
+    fn drawing_from_one_stream_leaves_the_others_alone() {
+        let mut quiet = GameRng::new(7);
+        let mut busy = GameRng::new(7);
+        draw(&mut busy, RngStream::SprayScatter);
+        assert_eq!(
+            draw(&mut busy, RngStream::LevelGeneration),
+            draw(&mut quiet, RngStream::LevelGeneration)
+        );
+    }
+}
@@ -0,0 +1,35 @@
+//! Reading assets from RON files, shared by the asset loaders of the game.
+
+use bevy::asset::io::Reader;
+use serde::de::DeserializeOwned;
+
+/// Errors from loading an asset from a RON file.
+#[derive(Debug)]
+pub enum RonLoaderError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    /// The file parsed, but what it describes can't be loaded.
+    Invalid(String),
+}
+
+impl std::fmt::Display for RonLoaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RonLoaderError::Io(error) => write!(f, "could not read file: {error}"),
+            RonLoaderError::Ron(error) => write!(f, "could not parse RON: {error}"),
+            RonLoaderError::Invalid(message) => write!(f, "invalid contents: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for RonLoaderError {}
+
+/// Reads all of `reader` and deserializes it from RON.
+pub async fn read_ron<T: DeserializeOwned>(reader: &mut dyn Reader) -> Result<T, RonLoaderError> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(RonLoaderError::Io)?;
+    ron::de::from_bytes(&bytes).map_err(RonLoaderError::Ron)
+}
@@ -1,6 +1,7 @@
 use rand::Rng;
 
-pub fn random_number(min: f32, max: f32) -> f32 {
-    let mut rng = rand::rng();
+/// A random number in `min..max`, drawn from `rng`, usually a stream of the
+/// [`GameRng`](super::rng::GameRng).
+pub fn random_number(rng: &mut impl Rng, min: f32, max: f32) -> f32 {
     return rng.random_range(min..max);
-}
\ No newline at end of file
+}
@@ -0,0 +1,405 @@
+//! Keyframed camera paths, played on the [`MainCamera`] in [`CameraState::Cinematic`].
+//!
+//! A [`CameraPath`] is loaded from a `.camera_path.ron` file in `assets/`. Between keyframes, the
+//! position follows a Catmull-Rom spline or Bezier curves, the rotation is slerped and the field
+//! of view is blended. Paths play on the [`VirtualClock`] the camera is [`OnClock`], so they can
+//! be paused, slowed down and sped up without touching the game's time. The showcase path gets a
+//! clock of its own, within the scenario clock.
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::CameraSystems;
+use crate::bevy_starter::ron_asset::{RonLoaderError, read_ron};
+use crate::simple_scene::game::{CameraState, MainCamera, spawn_main_camera};
+use crate::time::{ClockDomain, ClockTime, Clocks, OnClock, VirtualClock};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<CameraPath>()
+        .register_asset_loader(CameraPathLoader)
+        .add_systems(OnEnter(CameraState::Cinematic), start_camera_path)
+        .add_systems(OnExit(CameraState::Cinematic), restore_field_of_view)
+        .add_systems(
+            PostUpdate,
+            play_camera_path
+                .in_set(CameraSystems::Place)
+                .run_if(in_state(CameraState::Cinematic)),
+        );
+
+    if app.world().contains_resource::<AssetServer>() {
+        app.add_systems(Startup, load_showcase_path.after(spawn_main_camera));
+    }
+}
+
+/// The path played when entering [`CameraState::Cinematic`], relative to `assets/`.
+pub const SHOWCASE_PATH: &str = "camera_paths/showcase.camera_path.ron";
+
+/// How the position moves between the keyframes of a [`CameraPath`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathInterpolation {
+    Linear,
+    /// A spline through all keyframes, with tangents from the neighbouring keyframes.
+    #[default]
+    CatmullRom,
+    /// Cubic Bezier curves shaped by the handles of the keyframes. Missing handles are chosen to
+    /// match the Catmull-Rom spline.
+    Bezier,
+}
+
+/// One pose on a [`CameraPath`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraKeyframe {
+    /// When the camera reaches this keyframe, in seconds from the start of the path.
+    pub time: f32,
+    pub position: Vec3,
+    /// Where the camera looks. Takes precedence over `rotation`.
+    #[serde(default)]
+    pub look_at: Option<Vec3>,
+    #[serde(default)]
+    pub rotation: Option<Quat>,
+    /// Vertical field of view, in degrees.
+    #[serde(default = "default_fov")]
+    pub fov: f32,
+    /// Bezier handle towards the previous keyframe, relative to `position`.
+    #[serde(default)]
+    pub in_handle: Option<Vec3>,
+    /// Bezier handle towards the next keyframe, relative to `position`.
+    #[serde(default)]
+    pub out_handle: Option<Vec3>,
+}
+
+fn default_fov() -> f32 {
+    45.0
+}
+
+impl CameraKeyframe {
+    pub fn rotation(&self) -> Quat {
+        match (self.look_at, self.rotation) {
+            (Some(target), _) => {
+                Transform::from_translation(self.position)
+                    .looking_at(target, Vec3::Y)
+                    .rotation
+            }
+            (None, Some(rotation)) => rotation,
+            (None, None) => Quat::IDENTITY,
+        }
+    }
+}
+
+/// A keyframed camera path.
+#[derive(Asset, TypePath, Clone, Debug, Serialize, Deserialize)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+    #[serde(default)]
+    pub interpolation: PathInterpolation,
+    /// Start over at the end instead of leaving the cinematic.
+    #[serde(default)]
+    pub looping: bool,
+}
+
+/// A point on a [`CameraPath`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraPose {
+    pub transform: Transform,
+    /// Vertical field of view, in radians.
+    pub fov: f32,
+}
+
+impl CameraPath {
+    /// The time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Where on the path a player is after playing it for `elapsed` seconds, wrapping around if
+    /// it loops. `None` once a path that doesn't loop has finished. A looping path without any
+    /// length holds its only pose.
+    pub fn playhead(&self, elapsed: f32) -> Option<f32> {
+        let duration = self.duration();
+        if elapsed < duration {
+            Some(elapsed)
+        } else if !self.looping {
+            None
+        } else if duration > 0.0 {
+            Some(elapsed % duration)
+        } else {
+            Some(0.0)
+        }
+    }
+
+    /// The pose at `time` seconds into the path, or `None` if it has no keyframes.
+    pub fn sample(&self, time: f32) -> Option<CameraPose> {
+        let keyframes = &self.keyframes;
+        let last = keyframes.len().checked_sub(1)?;
+
+        // The segment from keyframe `i` to `i + 1` containing `time`.
+        let i = keyframes
+            .partition_point(|keyframe| keyframe.time <= time)
+            .saturating_sub(1)
+            .min(last.saturating_sub(1));
+        let (k1, k2) = (&keyframes[i], &keyframes[(i + 1).min(last)]);
+        let span = k2.time - k1.time;
+        let u = if span > 0.0 {
+            ((time - k1.time) / span).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        // The neighbours shaping the curve, repeating the ends.
+        let p0 = keyframes[i.saturating_sub(1)].position;
+        let p1 = k1.position;
+        let p2 = k2.position;
+        let p3 = keyframes[(i + 2).min(last)].position;
+
+        let position = match self.interpolation {
+            PathInterpolation::Linear => p1.lerp(p2, u),
+            PathInterpolation::CatmullRom => {
+                0.5 * (2.0 * p1
+                    + (p2 - p0) * u
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * u * u
+                    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * u * u * u)
+            }
+            PathInterpolation::Bezier => {
+                let c1 = p1 + k1.out_handle.unwrap_or((p2 - p0) / 6.0);
+                let c2 = p2 + k2.in_handle.unwrap_or((p1 - p3) / 6.0);
+                let v = 1.0 - u;
+                v * v * v * p1 + 3.0 * v * v * u * c1 + 3.0 * v * u * u * c2 + u * u * u * p2
+            }
+        };
+
+        Some(CameraPose {
+            transform: Transform::from_translation(position)
+                .with_rotation(k1.rotation().slerp(k2.rotation(), u)),
+            fov: (k1.fov + (k2.fov - k1.fov) * u).to_radians(),
+        })
+    }
+}
+
+/// Plays a [`CameraPath`] on the [`MainCamera`], at the speed of the clock it is [`OnClock`].
+#[derive(Component, Clone, Debug)]
+pub struct CameraPathPlayer {
+    pub path: Handle<CameraPath>,
+    elapsed_secs: f32,
+    /// The field of view to go back to after the cinematic.
+    previous_fov: Option<f32>,
+}
+
+impl CameraPathPlayer {
+    pub fn new(path: Handle<CameraPath>) -> Self {
+        Self {
+            path,
+            elapsed_secs: 0.0,
+            previous_fov: None,
+        }
+    }
+
+    /// Seconds played since the path started.
+    pub fn elapsed_secs(&self) -> f32 {
+        self.elapsed_secs
+    }
+}
+
+fn load_showcase_path(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    clocks: Option<Res<Clocks>>,
+    main_camera: Single<Entity, With<MainCamera>>,
+) {
+    let mut clock = VirtualClock::new();
+    clock.parent = clocks.map(|clocks| clocks.get(ClockDomain::Scenario));
+    let clock = commands.spawn((Name::new("Cinematic clock"), clock)).id();
+    commands.entity(*main_camera).insert((
+        CameraPathPlayer::new(asset_server.load(SHOWCASE_PATH)),
+        OnClock(clock),
+    ));
+}
+
+fn start_camera_path(
+    main_camera: Single<(&mut CameraPathPlayer, &Projection), With<MainCamera>>,
+) {
+    let (mut player, projection) = main_camera.into_inner();
+    player.elapsed_secs = 0.0;
+    if let Projection::Perspective(perspective) = projection {
+        player.previous_fov = Some(perspective.fov);
+    }
+}
+
+fn restore_field_of_view(
+    main_camera: Single<(&mut CameraPathPlayer, &mut Projection), With<MainCamera>>,
+) {
+    let (mut player, mut projection) = main_camera.into_inner();
+    if let (Some(fov), Projection::Perspective(perspective)) =
+        (player.previous_fov.take(), projection.as_mut())
+    {
+        perspective.fov = fov;
+    }
+}
+
+/// Advances the [`CameraPathPlayer`] and puts the [`MainCamera`] on its path. Leaves the
+/// cinematic once a path that doesn't loop has finished.
+fn play_camera_path(
+    clock: ClockTime,
+    paths: Res<Assets<CameraPath>>,
+    mut next_state: ResMut<NextState<CameraState>>,
+    main_camera: Single<
+        (
+            Entity,
+            &mut Transform,
+            &mut Projection,
+            &mut CameraPathPlayer,
+        ),
+        With<MainCamera>,
+    >,
+) {
+    let (entity, mut transform, mut projection, mut player) = main_camera.into_inner();
+    let Some(path) = paths.get(&player.path) else {
+        return;
+    };
+
+    let elapsed = player.elapsed_secs + clock.delta_secs(entity);
+    player.elapsed_secs = path.playhead(elapsed).unwrap_or_else(|| {
+        next_state.set(CameraState::StaticView);
+        path.duration()
+    });
+
+    let Some(pose) = path.sample(player.elapsed_secs) else {
+        return;
+    };
+    *transform = pose.transform;
+    if let Projection::Perspective(perspective) = projection.as_mut() {
+        perspective.fov = pose.fov;
+    }
+}
+
+/// Loads a [`CameraPath`] from a RON file.
+#[derive(Default)]
+struct CameraPathLoader;
+
+impl AssetLoader for CameraPathLoader {
+    type Asset = CameraPath;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut path: CameraPath = read_ron(reader).await?;
+        path.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(path)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["camera_path.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f32, position: Vec3, fov: f32) -> CameraKeyframe {
+        CameraKeyframe {
+            time,
+            position,
+            look_at: None,
+            rotation: Some(Quat::from_rotation_y(time)),
+            fov,
+            in_handle: None,
+            out_handle: None,
+        }
+    }
+
+    fn path(keyframes: Vec<CameraKeyframe>, interpolation: PathInterpolation) -> CameraPath {
+        CameraPath {
+            keyframes,
+            interpolation,
+            looping: false,
+        }
+    }
+
+    fn assert_pose(pose: CameraPose, keyframe: &CameraKeyframe) {
+        let transform = pose.transform;
+        assert!(
+            transform.translation.abs_diff_eq(keyframe.position, 1e-4),
+            "{} != {}",
+            transform.translation,
+            keyframe.position
+        );
+        assert!(transform.rotation.abs_diff_eq(keyframe.rotation(), 1e-4));
+        assert!((pose.fov - keyframe.fov.to_radians()).abs() < 1e-5);
+    }
+
+    const INTERPOLATIONS: [PathInterpolation; 3] = [
+        PathInterpolation::Linear,
+        PathInterpolation::CatmullRom,
+        PathInterpolation::Bezier,
+    ];
+
+    #[test]
+    fn keyframe_times_sample_the_keyframes() {
+        let keyframes = vec![
+            keyframe(0.0, Vec3::ZERO, 40.0),
+            keyframe(1.0, Vec3::new(2.0, 1.0, 0.0), 50.0),
+            keyframe(2.5, Vec3::new(3.0, 0.0, -4.0), 45.0),
+            keyframe(4.0, Vec3::new(-1.0, 2.0, -6.0), 60.0),
+        ];
+        for interpolation in INTERPOLATIONS {
+            let path = path(keyframes.clone(), interpolation);
+            for keyframe in &keyframes {
+                assert_pose(path.sample(keyframe.time).unwrap(), keyframe);
+            }
+        }
+    }
+
+    #[test]
+    fn samples_outside_the_path_clamp_to_its_ends() {
+        let first = keyframe(0.0, Vec3::ZERO, 40.0);
+        let last = keyframe(2.0, Vec3::new(2.0, 1.0, 0.0), 50.0);
+        for interpolation in INTERPOLATIONS {
+            let path = path(vec![first.clone(), last.clone()], interpolation);
+            assert_pose(path.sample(-1.0).unwrap(), &first);
+            assert_pose(path.sample(10.0).unwrap(), &last);
+        }
+    }
+
+    #[test]
+    fn empty_and_single_keyframe_paths_can_be_sampled() {
+        for interpolation in INTERPOLATIONS {
+            assert_eq!(path(Vec::new(), interpolation).sample(0.0), None);
+
+            let only = keyframe(0.0, Vec3::new(1.0, 2.0, 3.0), 50.0);
+            let path = path(vec![only.clone()], interpolation);
+            for time in [-1.0, 0.0, 1.0] {
+                assert_pose(path.sample(time).unwrap(), &only);
+            }
+        }
+    }
+
+    #[test]
+    fn looping_paths_wrap_around_and_others_finish() {
+        let mut path = path(
+            vec![
+                keyframe(0.0, Vec3::ZERO, 40.0),
+                keyframe(2.0, Vec3::X, 40.0),
+            ],
+            PathInterpolation::Linear,
+        );
+        assert_eq!(path.playhead(1.5), Some(1.5));
+        assert_eq!(path.playhead(2.0), None);
+
+        path.looping = true;
+        assert_eq!(path.playhead(2.5), Some(0.5));
+
+        // A looping path without any length holds its pose instead of finishing.
+        path.keyframes.truncate(1);
+        assert_eq!(path.playhead(0.5), Some(0.0));
+        path.looping = false;
+        assert_eq!(path.playhead(0.5), None);
+    }
+}
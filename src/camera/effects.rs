@@ -0,0 +1,442 @@
+//! Camera feel: head bob, landing dip, FOV kick and screen shake.
+//!
+//! Each effect is a component on the camera with its own settings, and can be turned off on its
+//! own with `enabled`, for example for players prone to motion sickness. The effects are added up
+//! into an offset layered on top of wherever the current [`CameraState`] placed the camera, and
+//! that offset is taken off again at the start of the next frame, so looking around and the
+//! placement never see it. The effects run on the scenario clock, so they freeze while the game is
+//! paused.
+
+use std::f32::consts::{PI, TAU};
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use super::{
+    CameraSystems,
+    rig::{CameraRig, RigBehavior},
+};
+use crate::character_controller::{Grounded, Sprint, UpDirection};
+use crate::simple_scene::game::{CameraState, MainCharacter};
+use crate::time::{ClockDomain, ClockTime};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(First, remove_camera_effects).add_systems(
+        PostUpdate,
+        (
+            update_head_bob,
+            update_landing_dip,
+            update_fov_kick,
+            update_screen_shake,
+            apply_camera_effects,
+        )
+            .chain()
+            .in_set(CameraSystems::Effects),
+    );
+}
+
+/// The offset the effects added to the camera this frame.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct CameraEffectOffset {
+    translation: Vec3,
+    /// Added to `translation` relative to the view.
+    view_translation: Vec3,
+    rotation: Quat,
+    /// Added to the vertical field of view, in radians.
+    fov: f32,
+}
+
+/// Bobs the first person camera up and down, and a little sideways, with the steps of the
+/// [`MainCharacter`] walking on the ground.
+#[derive(Component, Clone, Debug)]
+#[require(CameraEffectOffset)]
+pub struct HeadBob {
+    pub enabled: bool,
+    /// Height of the bob at `reference_speed`.
+    pub amplitude: f32,
+    /// Steps per unit walked.
+    pub step_frequency: f32,
+    /// The speed at which the bob has its full amplitude.
+    pub reference_speed: f32,
+    phase: f32,
+    /// How much of the amplitude is used, easing in and out as the character starts and stops.
+    intensity: f32,
+}
+
+impl Default for HeadBob {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            amplitude: 0.04,
+            step_frequency: 0.35,
+            reference_speed: 6.0,
+            phase: 0.0,
+            intensity: 0.0,
+        }
+    }
+}
+
+/// Dips the first person camera when the [`MainCharacter`] lands, deeper after longer falls.
+#[derive(Component, Clone, Debug)]
+#[require(CameraEffectOffset)]
+pub struct LandingDip {
+    pub enabled: bool,
+    /// Depth of the dip per unit of falling speed.
+    pub depth_per_speed: f32,
+    pub max_depth: f32,
+    /// Stiffness of the spring pulling the camera back up.
+    pub stiffness: f32,
+    /// Falling speed below which landing doesn't dip.
+    pub min_speed: f32,
+    fall_speed: f32,
+    offset: f32,
+    velocity: f32,
+}
+
+impl Default for LandingDip {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            depth_per_speed: 0.02,
+            max_depth: 0.3,
+            stiffness: 120.0,
+            min_speed: 3.0,
+            fall_speed: 0.0,
+            offset: 0.0,
+            velocity: 0.0,
+        }
+    }
+}
+
+/// Widens the field of view while the [`MainCharacter`] sprints.
+#[derive(Component, Clone, Debug)]
+#[require(CameraEffectOffset)]
+pub struct FovKick {
+    pub enabled: bool,
+    /// Added to the field of view while sprinting, in degrees.
+    pub sprint_degrees: f32,
+    /// How quickly the kick settles, per second.
+    pub smoothing: f32,
+    degrees: f32,
+}
+
+impl Default for FovKick {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sprint_degrees: 8.0,
+            smoothing: 6.0,
+            degrees: 0.0,
+        }
+    }
+}
+
+/// Trauma based screen shake: trauma is added by impacts and wears off over time, and the camera
+/// shakes with the square of it.
+#[derive(Component, Clone, Debug)]
+#[require(CameraEffectOffset)]
+pub struct ScreenShake {
+    pub enabled: bool,
+    /// Offset at full trauma.
+    pub max_offset: f32,
+    /// Rotation at full trauma, in radians.
+    pub max_angle: f32,
+    /// How fast the camera shakes, in shakes per second.
+    pub frequency: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+    /// Trauma added per unit of falling speed when the [`MainCharacter`] lands.
+    pub landing_trauma: f32,
+    trauma: f32,
+    time: f32,
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_offset: 0.15,
+            max_angle: 0.05,
+            frequency: 15.0,
+            decay: 1.5,
+            landing_trauma: 0.04,
+            trauma: 0.0,
+            time: 0.0,
+        }
+    }
+}
+
+impl ScreenShake {
+    /// Adds trauma, in `0..=1` altogether.
+    pub fn add_trauma(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).clamp(0.0, 1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+}
+
+/// A smooth pseudo-random value in `-1..=1`, different for every `seed`.
+fn shake_noise(time: f32, seed: f32) -> f32 {
+    ((time + seed * 7.13).sin() * 0.5
+        + (time * 2.31 + seed * 3.71).sin() * 0.3
+        + (time * 4.77 + seed * 1.93).sin() * 0.2)
+        .clamp(-1.0, 1.0)
+}
+
+fn first_person(rig: Option<&CameraRig>) -> bool {
+    rig.is_some_and(|rig| rig.behavior == RigBehavior::FirstPerson)
+}
+
+/// Takes last frame's offset off the camera again.
+fn remove_camera_effects(
+    mut cameras: Query<(&mut Transform, &mut Projection, &mut CameraEffectOffset)>,
+) {
+    for (mut transform, mut projection, mut offset) in &mut cameras {
+        let offset = std::mem::take(&mut *offset);
+        transform.translation -= offset.translation;
+        transform.rotation = offset.rotation.inverse() * transform.rotation;
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov -= offset.fov;
+        }
+    }
+}
+
+fn update_head_bob(
+    clock: ClockTime,
+    main_character: Single<(&LinearVelocity, &UpDirection, Has<Grounded>), With<MainCharacter>>,
+    mut cameras: Query<(&mut HeadBob, Option<&CameraRig>, &mut CameraEffectOffset)>,
+) {
+    let (linear_velocity, up, grounded) = *main_character;
+    let horizontal_speed = linear_velocity.reject_from_normalized(up.vector()).length();
+    let delta_secs = clock.domain_delta_secs(ClockDomain::Scenario);
+
+    for (mut bob, rig, mut offset) in &mut cameras {
+        let active = bob.enabled && grounded && first_person(rig);
+        let target = if active {
+            (horizontal_speed / bob.reference_speed).min(1.0)
+        } else {
+            0.0
+        };
+        bob.intensity += (target - bob.intensity) * (1.0 - (-8.0 * delta_secs).exp());
+        // Half a turn per step.
+        bob.phase = (bob.phase + horizontal_speed * bob.step_frequency * PI * delta_secs) % TAU;
+
+        // Up and down once per step, side to side once per stride of two steps.
+        let amplitude = bob.amplitude * bob.intensity;
+        let vertical = bob.phase.sin().abs() * amplitude;
+        let sideways = bob.phase.sin() * amplitude * 0.5;
+        offset.translation += *up.0 * vertical;
+        offset.view_translation.x += sideways;
+    }
+}
+
+fn update_landing_dip(
+    clock: ClockTime,
+    main_character: Single<(&LinearVelocity, &UpDirection, Has<Grounded>), With<MainCharacter>>,
+    just_landed: Query<(), (With<MainCharacter>, Added<Grounded>)>,
+    mut cameras: Query<(
+        &mut LandingDip,
+        Option<&mut ScreenShake>,
+        Option<&CameraRig>,
+        &mut CameraEffectOffset,
+    )>,
+) {
+    let (linear_velocity, up, grounded) = *main_character;
+    let landed = !just_landed.is_empty();
+    let delta_secs = clock.domain_delta_secs(ClockDomain::Scenario);
+
+    for (mut dip, shake, rig, mut offset) in &mut cameras {
+        if landed && dip.fall_speed >= dip.min_speed {
+            if dip.enabled && first_person(rig) {
+                dip.velocity -= dip.fall_speed * dip.depth_per_speed * dip.stiffness.sqrt();
+            }
+            if let Some(mut shake) = shake {
+                let trauma = (dip.fall_speed - dip.min_speed) * shake.landing_trauma;
+                shake.add_trauma(trauma);
+            }
+        }
+        // Grounding zeroes the velocity into the ground, so remember the speed of the fall.
+        dip.fall_speed = if grounded {
+            0.0
+        } else {
+            (-linear_velocity.dot(up.vector())).max(0.0)
+        };
+
+        // A critically damped spring back to rest.
+        let acceleration = -dip.stiffness * dip.offset - 2.0 * dip.stiffness.sqrt() * dip.velocity;
+        dip.velocity += acceleration * delta_secs;
+        dip.offset = (dip.offset + dip.velocity * delta_secs).max(-dip.max_depth);
+        offset.translation += *up.0 * dip.offset;
+    }
+}
+
+fn update_fov_kick(
+    clock: ClockTime,
+    state: Res<State<CameraState>>,
+    main_character: Single<Option<&Sprint>, With<MainCharacter>>,
+    mut cameras: Query<(&mut FovKick, &mut CameraEffectOffset)>,
+) {
+    let sprinting = main_character.is_some_and(Sprint::is_sprinting);
+    let delta_secs = clock.domain_delta_secs(ClockDomain::Scenario);
+
+    for (mut kick, mut offset) in &mut cameras {
+        let target = if kick.enabled && sprinting && state.controls_character() {
+            kick.sprint_degrees
+        } else {
+            0.0
+        };
+        let blend = 1.0 - (-kick.smoothing * delta_secs).exp();
+        kick.degrees += (target - kick.degrees) * blend;
+        offset.fov += kick.degrees.to_radians();
+    }
+}
+
+fn update_screen_shake(
+    clock: ClockTime,
+    mut cameras: Query<(&mut ScreenShake, &mut CameraEffectOffset)>,
+) {
+    let delta_secs = clock.domain_delta_secs(ClockDomain::Scenario);
+    for (mut shake, mut offset) in &mut cameras {
+        shake.time += delta_secs * shake.frequency;
+        shake.trauma = (shake.trauma - shake.decay * delta_secs).max(0.0);
+        if !shake.enabled {
+            continue;
+        }
+
+        let strength = shake.trauma * shake.trauma;
+        let t = shake.time;
+        offset.view_translation +=
+            Vec3::new(shake_noise(t, 1.0), shake_noise(t, 2.0), 0.0) * shake.max_offset * strength;
+        offset.rotation *= Quat::from_euler(
+            EulerRot::YXZ,
+            shake_noise(t, 3.0) * shake.max_angle * strength,
+            shake_noise(t, 4.0) * shake.max_angle * strength,
+            shake_noise(t, 5.0) * shake.max_angle * strength,
+        );
+    }
+}
+
+/// Layers the summed offset of all effects on top of the placed camera.
+fn apply_camera_effects(
+    mut cameras: Query<(&mut Transform, &mut Projection, &mut CameraEffectOffset)>,
+) {
+    for (mut transform, mut projection, mut offset) in &mut cameras {
+        // Keep the offset in world space, to take exactly this off again next frame.
+        offset.translation += transform.rotation * std::mem::take(&mut offset.view_translation);
+        transform.translation += offset.translation;
+        offset.rotation = transform.rotation * offset.rotation * transform.rotation.inverse();
+        transform.rotation = offset.rotation * transform.rotation;
+        if let Projection::Perspective(perspective) = projection.as_mut() {
+            perspective.fov += offset.fov;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::time::{ClockPlugin, Clocks, VirtualClock};
+
+    /// An app with a walking [`MainCharacter`] and a first person camera at `placed` with every
+    /// effect in full swing.
+    fn app(placed: Transform) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, ClockPlugin, plugin))
+            .insert_state(CameraState::FirstPersonView)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1.0 / 60.0,
+            )));
+        app.world_mut().spawn((
+            MainCharacter,
+            LinearVelocity(Vec3::new(4.0, 0.0, 3.0)),
+            UpDirection::default(),
+            Grounded,
+        ));
+        let camera = app
+            .world_mut()
+            .spawn((
+                placed,
+                Projection::Perspective(default()),
+                CameraRig::new(RigBehavior::FirstPerson),
+                HeadBob {
+                    intensity: 1.0,
+                    phase: 1.0,
+                    ..default()
+                },
+                LandingDip {
+                    offset: -0.1,
+                    ..default()
+                },
+                FovKick {
+                    degrees: 8.0,
+                    ..default()
+                },
+                ScreenShake {
+                    trauma: 1.0,
+                    ..default()
+                },
+            ))
+            .id();
+        (app, camera)
+    }
+
+    fn fov(app: &App, camera: Entity) -> f32 {
+        match app.world().get::<Projection>(camera).unwrap() {
+            Projection::Perspective(perspective) => perspective.fov,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn effects_come_off_again_at_the_start_of_the_next_frame() {
+        let placed =
+            Transform::from_xyz(1.0, 2.0, 3.0).looking_at(Vec3::new(4.0, 0.0, -2.0), Vec3::Y);
+        let (mut app, camera) = app(placed);
+        let placed_fov = fov(&app, camera);
+
+        // Nothing places the camera here, so it has to be back at `placed` after every `First`.
+        for _ in 0..3 {
+            app.update();
+            let transform = *app.world().get::<Transform>(camera).unwrap();
+            assert!(!transform.translation.abs_diff_eq(placed.translation, 1e-3));
+            assert!(!transform.rotation.abs_diff_eq(placed.rotation, 1e-4));
+            assert!(fov(&app, camera) > placed_fov);
+
+            app.world_mut().run_schedule(First);
+            let transform = *app.world().get::<Transform>(camera).unwrap();
+            assert!(
+                transform.translation.abs_diff_eq(placed.translation, 1e-5),
+                "{} != {}",
+                transform.translation,
+                placed.translation
+            );
+            assert!(transform.rotation.abs_diff_eq(placed.rotation, 1e-5));
+            assert!((fov(&app, camera) - placed_fov).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn effects_freeze_while_the_scenario_is_paused() {
+        let (mut app, camera) = app(Transform::default());
+        app.update();
+        let scenario = app.world().resource::<Clocks>().get(ClockDomain::Scenario);
+        app.world_mut()
+            .get_mut::<VirtualClock>(scenario)
+            .unwrap()
+            .toggle_pause();
+        app.update();
+
+        let shake = app.world().get::<ScreenShake>(camera).unwrap().clone();
+        for _ in 0..5 {
+            app.update();
+        }
+        let paused = app.world().get::<ScreenShake>(camera).unwrap();
+        assert_eq!((paused.time, paused.trauma), (shake.time, shake.trauma));
+    }
+}
@@ -0,0 +1,191 @@
+//! The first person view, and handing control over between the camera and the [`MainCharacter`].
+//!
+//! In the first person view the [`MainCamera`] sits at the [`EyeOffset`] of the character. When
+//! the player takes over the character from a view that doesn't control it, the character stays
+//! where it is and the [`CameraTransition`](super::transition::CameraTransition) blends the camera
+//! to the character's eyes from where it was. Only a character stuck inside geometry is moved, to
+//! the nearest free spot above it.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+use super::CameraSystems;
+use crate::character_controller::{UpDirection, find_safe_position, is_penetrating};
+use crate::simple_scene::game::{CameraState, MainCamera, MainCharacter};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        free_stuck_character.run_if(on_event::<StateTransitionEvent<CameraState>>),
+    )
+    .add_systems(
+        PostUpdate,
+        follow_eyes
+            .in_set(CameraSystems::Place)
+            .run_if(in_state(CameraState::FirstPersonView)),
+    );
+}
+
+/// Where the eyes of a character are, relative to its origin and turned with it.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct EyeOffset(pub Vec3);
+
+impl Default for EyeOffset {
+    fn default() -> Self {
+        Self(Vec3::new(0.0, 0.6, 0.0))
+    }
+}
+
+impl EyeOffset {
+    /// The position of the eyes of a character at `transform`.
+    pub fn eye_position(&self, transform: &Transform) -> Vec3 {
+        transform.translation + transform.rotation * self.0
+    }
+}
+
+/// Puts the [`MainCamera`] at the eyes of the [`MainCharacter`].
+fn follow_eyes(
+    mut main_camera: Single<&mut Transform, With<MainCamera>>,
+    main_character: Single<
+        (&Transform, Option<&EyeOffset>),
+        (With<MainCharacter>, Without<MainCamera>),
+    >,
+) {
+    let (character_transform, eye_offset) = *main_character;
+    main_camera.translation = eye_offset
+        .copied()
+        .unwrap_or_default()
+        .eye_position(character_transform);
+}
+
+/// Frees the [`MainCharacter`] when the player takes control of it while it is inside geometry,
+/// e.g. after a block was placed on it in a detached view. A character that isn't stuck is left
+/// alone.
+fn free_stuck_character(
+    mut transitions: EventReader<StateTransitionEvent<CameraState>>,
+    spatial_query: SpatialQuery,
+    sensors: Query<(), With<Sensor>>,
+    main_character: Single<
+        (
+            Entity,
+            &Collider,
+            &Rotation,
+            &UpDirection,
+            &mut Position,
+            &mut LinearVelocity,
+        ),
+        With<MainCharacter>,
+    >,
+) {
+    let taking_over = transitions.read().any(|transition| {
+        let controlled = |state: &Option<CameraState>| {
+            state.as_ref().is_some_and(CameraState::controls_character)
+        };
+        !controlled(&transition.exited) && controlled(&transition.entered)
+    });
+    if !taking_over {
+        return;
+    }
+
+    let (entity, collider, rotation, up, mut position, mut linear_velocity) =
+        main_character.into_inner();
+    let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+    if !is_penetrating(
+        &spatial_query,
+        &sensors,
+        collider,
+        rotation.0,
+        position.0,
+        &filter,
+    ) {
+        return;
+    }
+    match find_safe_position(
+        &spatial_query,
+        &sensors,
+        collider,
+        rotation.0,
+        position.0,
+        up.0,
+        &filter,
+    ) {
+        Some(safe_position) => {
+            position.0 = safe_position;
+            linear_velocity.0 = Vector::ZERO;
+        }
+        None => warn!("The main character is stuck inside geometry with no free spot above it"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+    use super::*;
+
+    fn take_over(app: &mut App) {
+        for state in [CameraState::StaticView, CameraState::FirstPersonView] {
+            app.world_mut()
+                .resource_mut::<NextState<CameraState>>()
+                .set(state);
+            app.update();
+        }
+    }
+
+    #[test]
+    fn taking_over_frees_a_character_stuck_in_a_block() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            StatesPlugin,
+            PhysicsPlugins::default(),
+            plugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_state::<CameraState>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )));
+        app.world_mut().spawn((MainCamera, Transform::default()));
+        // A block with its top at 2, and a standing capsule with its feet at 0.5 inside it.
+        let block_top = 2.0;
+        app.world_mut().spawn((
+            RigidBody::Static,
+            Collider::cuboid(4.0, block_top, 4.0),
+            Position(Vector::Y * block_top / 2.0),
+            Transform::from_xyz(0.0, block_top / 2.0, 0.0),
+        ));
+        let standing_height = 0.9;
+        let character = app
+            .world_mut()
+            .spawn((
+                MainCharacter,
+                RigidBody::Kinematic,
+                Collider::capsule(0.4, 1.0),
+                UpDirection::default(),
+                Position(Vector::new(0.5, 1.4, -0.5)),
+                Transform::from_xyz(0.5, 1.4, -0.5),
+            ))
+            .id();
+        let position = |app: &App| app.world().get::<Position>(character).unwrap().0;
+        for _ in 0..3 {
+            app.update();
+        }
+
+        take_over(&mut app);
+        let freed = position(&app);
+        assert!(
+            (freed.y - (block_top + standing_height)).abs() < 0.05,
+            "{freed}"
+        );
+        assert_eq!((freed.x, freed.z), (0.5, -0.5));
+
+        // Once it is free, taking over again leaves it where it is.
+        take_over(&mut app);
+        assert_eq!(position(&app), freed);
+    }
+}
@@ -0,0 +1,219 @@
+//! Framing [`CameraTarget`]s in the static view.
+//!
+//! The bounds of all targets are combined into a bounding sphere, and the [`MainCamera`] is
+//! placed along its [`StaticFraming`] direction just far enough back for the sphere to fit the
+//! view frustum with a margin. The camera eases towards that pose, so it follows targets that
+//! move.
+
+use avian3d::prelude::ColliderAabb;
+use bevy::{prelude::*, render::primitives::Aabb};
+
+use super::CameraSystems;
+use crate::simple_scene::game::{CameraState, MainCamera};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        frame_camera_targets
+            .in_set(CameraSystems::Place)
+            .run_if(in_state(CameraState::StaticView).and(any_with_component::<CameraTarget>)),
+    );
+}
+
+/// Marks an entity the static view keeps in frame.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct CameraTarget;
+
+/// How the [`MainCamera`] frames [`CameraTarget`]s in the static view.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct StaticFraming {
+    /// The direction the camera looks in.
+    pub view_direction: Dir3,
+    /// The fraction of the field of view left free around the targets.
+    pub margin: f32,
+    /// How quickly the camera settles on a new framing, per second.
+    pub smoothing: f32,
+}
+
+impl Default for StaticFraming {
+    fn default() -> Self {
+        Self {
+            view_direction: Dir3::new(Vec3::new(0.0, -3.0, -8.0)).unwrap(),
+            margin: 0.15,
+            smoothing: 4.0,
+        }
+    }
+}
+
+/// The world-space bounds of a target: its collider, its mesh, or just its position.
+fn target_bounds(
+    collider_aabb: Option<&ColliderAabb>,
+    mesh_aabb: Option<&Aabb>,
+    global_transform: &GlobalTransform,
+) -> (Vec3, Vec3) {
+    if let Some(aabb) = collider_aabb {
+        return (aabb.min, aabb.max);
+    }
+    let Some(aabb) = mesh_aabb else {
+        let point = global_transform.translation();
+        return (point, point);
+    };
+
+    let (center, half_extents) = (Vec3::from(aabb.center), Vec3::from(aabb.half_extents));
+    let mut min = Vec3::INFINITY;
+    let mut max = Vec3::NEG_INFINITY;
+    for corner in 0..8 {
+        let sign = Vec3::new(
+            if corner & 1 == 0 { -1.0 } else { 1.0 },
+            if corner & 2 == 0 { -1.0 } else { 1.0 },
+            if corner & 4 == 0 { -1.0 } else { 1.0 },
+        );
+        let point = global_transform.transform_point(center + half_extents * sign);
+        min = min.min(point);
+        max = max.max(point);
+    }
+    (min, max)
+}
+
+/// Eases the [`MainCamera`] towards the pose that fits all [`CameraTarget`]s in view.
+fn frame_camera_targets(
+    time: Res<Time>,
+    targets: Query<(Option<&ColliderAabb>, Option<&Aabb>, &GlobalTransform), With<CameraTarget>>,
+    main_camera: Single<
+        (&mut Transform, &Projection, Option<&StaticFraming>),
+        (With<MainCamera>, Without<CameraTarget>),
+    >,
+) {
+    let (mut transform, projection, framing) = main_camera.into_inner();
+    let framing = framing.copied().unwrap_or_default();
+
+    let (min, max) = targets.iter().fold(
+        (Vec3::INFINITY, Vec3::NEG_INFINITY),
+        |(min, max), (collider_aabb, mesh_aabb, global_transform)| {
+            let (target_min, target_max) =
+                target_bounds(collider_aabb, mesh_aabb, global_transform);
+            (min.min(target_min), max.max(target_max))
+        },
+    );
+    let center = (min + max) / 2.0;
+    let radius = ((max - min).length() / 2.0).max(0.1);
+
+    // The narrower of the two fields of view decides how far back the camera has to be.
+    let half_fov = match projection {
+        Projection::Perspective(perspective) => {
+            let vertical = perspective.fov / 2.0;
+            let horizontal = (vertical.tan() * perspective.aspect_ratio).atan();
+            vertical.min(horizontal)
+        }
+        _ => std::f32::consts::FRAC_PI_4,
+    };
+    let half_angle = half_fov * (1.0 - framing.margin).clamp(0.05, 1.0);
+    let distance = radius / half_angle.sin();
+
+    let target = Transform::from_translation(center - framing.view_direction * distance)
+        .looking_to(framing.view_direction, Vec3::Y);
+
+    let blend = 1.0 - (-framing.smoothing * time.delta_secs()).exp();
+    transform.translation = transform.translation.lerp(target.translation, blend);
+    transform.rotation = transform.rotation.slerp(target.rotation, blend);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{
+        render::camera::CameraProjection, state::app::StatesPlugin, time::TimeUpdateStrategy,
+    };
+
+    use super::*;
+
+    /// Frames targets with the given bounds until the camera has settled, and checks that they
+    /// fit the view with the margin.
+    fn assert_framed(bounds: &[(Vec3, Vec3)]) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin, StatesPlugin, plugin))
+            .init_state::<CameraState>()
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1.0 / 60.0,
+            )));
+        let projection = PerspectiveProjection {
+            aspect_ratio: 16.0 / 9.0,
+            ..default()
+        };
+        let framing = StaticFraming {
+            smoothing: 50.0,
+            ..default()
+        };
+        let camera = app
+            .world_mut()
+            .spawn((
+                MainCamera,
+                Transform::default(),
+                Projection::Perspective(projection.clone()),
+                framing,
+            ))
+            .id();
+        for &(min, max) in bounds {
+            app.world_mut().spawn((
+                CameraTarget,
+                Transform::default(),
+                ColliderAabb { min, max },
+            ));
+        }
+        for _ in 0..120 {
+            app.update();
+        }
+
+        let transform = *app.world().get::<Transform>(camera).unwrap();
+        let (min, max) = bounds.iter().fold(
+            (Vec3::INFINITY, Vec3::NEG_INFINITY),
+            |(min, max), &(target_min, target_max)| (min.min(target_min), max.max(target_max)),
+        );
+        let center = (min + max) / 2.0;
+        let radius = (max - min).length() / 2.0;
+
+        // The sphere takes up no more than the view narrowed by the margin.
+        let half_fov = projection.fov / 2.0;
+        let to_center = center - transform.translation;
+        let off_axis = transform.forward().angle_between(to_center);
+        let angular_radius = (radius / to_center.length()).asin();
+        assert!(off_axis < 1e-3, "{off_axis}");
+        assert!(
+            angular_radius <= half_fov * (1.0 - framing.margin) + 1e-3,
+            "{angular_radius}"
+        );
+
+        // So every corner of the targets is on screen, away from its edges.
+        let clip_from_view = projection.get_clip_from_view();
+        let view_from_world = transform.compute_matrix().inverse();
+        let edge = (half_fov * (1.0 - framing.margin)).tan() / half_fov.tan();
+        for &(min, max) in bounds {
+            for corner in 0..8 {
+                let point = Vec3::new(
+                    if corner & 1 == 0 { min.x } else { max.x },
+                    if corner & 2 == 0 { min.y } else { max.y },
+                    if corner & 4 == 0 { min.z } else { max.z },
+                );
+                let ndc = clip_from_view.project_point3(view_from_world.transform_point3(point));
+                assert!(
+                    ndc.x.abs() <= edge + 1e-3 && ndc.y.abs() <= edge + 1e-3,
+                    "{point} at {ndc}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn one_target_fits_the_view() {
+        assert_framed(&[(Vec3::new(-1.0, 0.0, -1.0), Vec3::new(1.0, 2.0, 1.0))]);
+    }
+
+    #[test]
+    fn two_targets_fit_the_view() {
+        assert_framed(&[
+            (Vec3::new(-6.0, 0.0, -1.0), Vec3::new(-5.0, 1.0, 0.0)),
+            (Vec3::new(4.0, 0.0, 3.0), Vec3::new(5.0, 3.0, 4.0)),
+        ]);
+    }
+}
@@ -1,9 +1,44 @@
 use bevy::prelude::*;
 
-pub mod fps_controller;
+pub mod cinematic;
+pub mod effects;
+pub mod first_person;
+pub mod framing;
+pub mod rig;
+pub mod third_person;
+pub mod transition;
+
+/// Ordering of the systems placing the [`MainCamera`](crate::simple_scene::game::MainCamera)
+/// every frame, in `PostUpdate`.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CameraSystems {
+    /// Puts the camera where the current `CameraState` wants it.
+    Place,
+    /// Blends from the previous `CameraState` after switching.
+    Blend,
+    /// Layers camera feel effects on top.
+    Effects,
+}
 
 pub fn add_all_plugins(app: &mut App) {
-    app.add_plugins(fps_controller::plugin);
+    app.configure_sets(
+        PostUpdate,
+        (
+            CameraSystems::Place,
+            CameraSystems::Blend,
+            CameraSystems::Effects,
+        )
+            .chain()
+            .run_if(rig::rig_attached)
+            .before(TransformSystem::TransformPropagate),
+    );
+    app.add_plugins(cinematic::plugin);
+    app.add_plugins(effects::plugin);
+    app.add_plugins(first_person::plugin);
+    app.add_plugins(framing::plugin);
+    app.add_plugins(rig::plugin);
+    app.add_plugins(third_person::plugin);
+    app.add_plugins(transition::plugin);
 }
 
 pub struct CameraPlugin;
@@ -0,0 +1,266 @@
+//! The camera rig: how cameras are turned and moved by the player.
+//!
+//! Every camera with a [`CameraRig`] gets its behaviour from the rig: turned around the
+//! [`MainCharacter`] in the first and third person views, or flown around freely. All behaviours
+//! share the sensitivity and inversion of [`LookSettings`]. The rig of the [`MainCamera`] follows
+//! the [`CameraState`], except while it is [`DetachedRig`] as a free-fly debug camera, which
+//! leaves the state alone and hands control back when reattached.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    input::mouse::MouseMotion,
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow},
+};
+
+use crate::character_controller::UpDirection;
+use crate::input::{ActionState, GameAction, GameAxis};
+use crate::simple_scene::game::{CameraState, MainCamera, MainCharacter};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<LookSettings>()
+        .add_systems(PreUpdate, look_around)
+        .add_systems(
+            Update,
+            (
+                cursor_grab,
+                (
+                    toggle_free_fly,
+                    follow_camera_state.run_if(state_changed::<CameraState>),
+                )
+                    .chain(),
+                fly,
+            ),
+        );
+}
+
+/// Mouse sensitivity and inversion, shared by all [`RigBehavior`]s.
+#[derive(Resource, Clone, Debug)]
+pub struct LookSettings {
+    pub sensitivity: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+}
+
+impl Default for LookSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.00005,
+            invert_x: false,
+            invert_y: false,
+        }
+    }
+}
+
+/// How a [`CameraRig`] turns and moves its camera.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RigBehavior {
+    /// The camera is placed by something else, like the static view or a camera path.
+    #[default]
+    Fixed,
+    /// Looks around the up direction of the [`MainCharacter`], from its eyes.
+    FirstPerson,
+    /// Looks around the up direction of the [`MainCharacter`], while a spring arm keeps the
+    /// camera behind it.
+    Orbit,
+    /// Flies around freely, looking with the cursor grabbed or the right mouse button held.
+    Fly,
+}
+
+impl RigBehavior {
+    /// The behaviour of the [`MainCamera`] in a [`CameraState`].
+    pub fn for_state(state: &CameraState) -> Self {
+        match state {
+            CameraState::FirstPersonView => RigBehavior::FirstPerson,
+            CameraState::ThirdPersonView => RigBehavior::Orbit,
+            CameraState::StaticView | CameraState::Cinematic => RigBehavior::Fixed,
+        }
+    }
+}
+
+/// Turns and moves a camera according to its [`RigBehavior`].
+#[derive(Component, Clone, Debug)]
+pub struct CameraRig {
+    pub behavior: RigBehavior,
+    /// Speed of [`RigBehavior::Fly`], in units per second.
+    pub fly_speed: f32,
+    /// Multiplies the fly speed while sprinting.
+    pub fly_boost: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            behavior: RigBehavior::default(),
+            fly_speed: 12.0,
+            fly_boost: 3.0,
+        }
+    }
+}
+
+impl CameraRig {
+    pub fn new(behavior: RigBehavior) -> Self {
+        Self {
+            behavior,
+            ..default()
+        }
+    }
+}
+
+/// A camera flown freely for debugging, detached from the player. Holds the behaviour to go back
+/// to, which keeps following the [`CameraState`] in the meantime.
+#[derive(Component, Clone, Copy, Debug)]
+#[component(storage = "SparseSet")]
+pub struct DetachedRig {
+    attached: RigBehavior,
+}
+
+/// Run condition that is true while the [`MainCamera`] is not detached.
+pub fn rig_attached(detached: Query<(), (With<MainCamera>, With<DetachedRig>)>) -> bool {
+    detached.is_empty()
+}
+
+fn follow_camera_state(
+    state: Res<State<CameraState>>,
+    mut main_camera: Query<(&mut CameraRig, Option<&mut DetachedRig>), With<MainCamera>>,
+) {
+    let behavior = RigBehavior::for_state(state.get());
+    for (mut rig, detached) in &mut main_camera {
+        match detached {
+            Some(mut detached) => detached.attached = behavior,
+            None => rig.behavior = behavior,
+        }
+    }
+}
+
+fn toggle_free_fly(
+    mut commands: Commands,
+    action_state: Res<ActionState>,
+    mut main_camera: Query<(Entity, &mut CameraRig, Option<&DetachedRig>), With<MainCamera>>,
+) {
+    if !action_state.just_pressed(GameAction::ToggleFreeFly) {
+        return;
+    }
+    for (entity, mut rig, detached) in &mut main_camera {
+        match detached {
+            Some(detached) => {
+                rig.behavior = detached.attached;
+                commands.entity(entity).remove::<DetachedRig>();
+            }
+            None => {
+                commands.entity(entity).insert(DetachedRig {
+                    attached: rig.behavior,
+                });
+                rig.behavior = RigBehavior::Fly;
+            }
+        }
+    }
+}
+
+/// Turns cameras by the mouse motion, if their [`RigBehavior`] looks around.
+///
+/// Yaw turns around the up direction of the [`MainCharacter`] and pitch is clamped relative to
+/// it, so looking around works the same on walls and ceilings of gravity fields. Flying cameras
+/// turn around the world's up.
+fn look_around(
+    settings: Res<LookSettings>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut cameras: Query<(&mut Transform, &CameraRig)>,
+    main_character: Query<&UpDirection, With<MainCharacter>>,
+) {
+    let Ok(window) = primary_window.single() else {
+        warn!("Primary window not found for `look_around`!");
+        return;
+    };
+    let character_up = main_character.single().map_or(Dir3::Y, |up| up.0);
+    let grabbed = window.cursor_options.grab_mode != CursorGrabMode::None;
+
+    // Using smallest of height or width ensures equal vertical and horizontal sensitivity
+    let window_scale = window.height().min(window.width());
+    let delta = mouse_motion.read().map(|motion| motion.delta).sum::<Vec2>()
+        * settings.sensitivity
+        * window_scale;
+    let delta = Vec2::new(
+        if settings.invert_x { -delta.x } else { delta.x },
+        if settings.invert_y { -delta.y } else { delta.y },
+    );
+
+    for (mut transform, rig) in &mut cameras {
+        let (up, looking) = match rig.behavior {
+            RigBehavior::Fixed => continue,
+            RigBehavior::FirstPerson | RigBehavior::Orbit => (character_up, grabbed),
+            RigBehavior::Fly => (
+                Dir3::Y,
+                grabbed || mouse_buttons.pressed(MouseButton::Right),
+            ),
+        };
+
+        if looking && delta != Vec2::ZERO {
+            let pitch = FRAC_PI_2 - transform.forward().angle_between(*up);
+            let new_pitch = (pitch - delta.y.to_radians()).clamp(-1.54, 1.54);
+            transform.rotate_axis(up, -delta.x.to_radians());
+            transform.rotate_local_x(new_pitch - pitch);
+        }
+
+        // Keep the camera level with the up direction, also while it turns with gravity.
+        let forward = transform.forward();
+        transform.look_to(forward, up);
+    }
+}
+
+/// Moves flying cameras along the view with the movement axes. Jump flies up, crouch flies down
+/// and sprint speeds up.
+fn fly(
+    time: Res<Time>,
+    action_state: Res<ActionState>,
+    mut cameras: Query<(&mut Transform, &CameraRig)>,
+) {
+    let input = action_state.axis_pair(GameAxis::MoveX, GameAxis::MoveY);
+    let vertical = action_state.pressed(GameAction::Jump) as i8 as f32
+        - action_state.pressed(GameAction::Crouch) as i8 as f32;
+    let boosted = action_state.pressed(GameAction::Sprint);
+
+    for (mut transform, rig) in &mut cameras {
+        if rig.behavior != RigBehavior::Fly {
+            continue;
+        }
+        let direction =
+            transform.right() * input.x + transform.forward() * input.y + Vec3::Y * vertical;
+        let speed = if boosted {
+            rig.fly_speed * rig.fly_boost
+        } else {
+            rig.fly_speed
+        };
+        transform.translation += direction.clamp_length_max(1.0) * speed * time.delta_secs();
+    }
+}
+
+fn cursor_grab(
+    action_state: Res<ActionState>,
+    mut primary_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if let Ok(mut window) = primary_window.single_mut() {
+        if action_state.just_pressed(GameAction::ToggleCursorGrab) {
+            toggle_grab_cursor(&mut window);
+        }
+    } else {
+        warn!("Primary window not found for `cursor_grab`!");
+    }
+}
+
+/// Grabs/ungrabs mouse cursor
+fn toggle_grab_cursor(window: &mut Window) {
+    match window.cursor_options.grab_mode {
+        CursorGrabMode::None => {
+            window.cursor_options.grab_mode = CursorGrabMode::Confined;
+            window.cursor_options.visible = false;
+        }
+        _ => {
+            window.cursor_options.grab_mode = CursorGrabMode::None;
+            window.cursor_options.visible = true;
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! The third person view: an orbit camera on a spring arm behind the [`MainCharacter`].
+//!
+//! The camera keeps the rotation from looking around and sits at the end of an arm reaching back
+//! from a pivot above the character's shoulder. The arm is shape cast against the world, shortens
+//! at once when something gets in the way, and springs back out smoothly once it is clear.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use super::CameraSystems;
+use crate::character_controller::UpDirection;
+use crate::input::{ActionState, GameAction, GameAxis};
+use crate::simple_scene::game::{CameraState, MainCamera, MainCharacter};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        zoom_and_swap_shoulder.run_if(in_state(CameraState::ThirdPersonView)),
+    )
+    .add_systems(
+        PostUpdate,
+        follow_on_spring_arm
+            .in_set(CameraSystems::Place)
+            .run_if(in_state(CameraState::ThirdPersonView)),
+    );
+}
+
+/// Holds the [`MainCamera`] behind the [`MainCharacter`] in the third person view.
+#[derive(Component, Clone, Debug)]
+pub struct SpringArm {
+    /// Height of the pivot above the center of the character.
+    pub pivot_height: f32,
+    /// Sideways offset of the pivot, to look over the shoulder.
+    pub shoulder_offset: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    /// Change of the arm length per step of [`GameAxis::Zoom`].
+    pub zoom_step: f32,
+    /// How quickly zooming, the arm springing back out and shoulder swaps settle, per second.
+    pub smoothing: f32,
+    /// Radius of the sphere cast along the arm, which keeps the near plane out of walls.
+    pub probe_radius: f32,
+    target_distance: f32,
+    distance: f32,
+    shoulder_side: f32,
+    shoulder: f32,
+}
+
+impl Default for SpringArm {
+    fn default() -> Self {
+        Self {
+            pivot_height: 0.6,
+            shoulder_offset: 0.5,
+            min_distance: 1.0,
+            max_distance: 8.0,
+            zoom_step: 0.5,
+            smoothing: 10.0,
+            probe_radius: 0.2,
+            target_distance: 4.0,
+            distance: 4.0,
+            shoulder_side: 1.0,
+            shoulder: 0.5,
+        }
+    }
+}
+
+impl SpringArm {
+    /// The arm length asked for by zooming, before collisions shorten it.
+    pub fn target_distance(&self) -> f32 {
+        self.target_distance
+    }
+
+    pub fn swap_shoulder(&mut self) {
+        self.shoulder_side = -self.shoulder_side;
+    }
+}
+
+fn zoom_and_swap_shoulder(action_state: Res<ActionState>, mut arms: Query<&mut SpringArm>) {
+    let zoom = action_state.axis(GameAxis::Zoom);
+    let swap = action_state.just_pressed(GameAction::SwapShoulder);
+
+    for mut arm in &mut arms {
+        if zoom != 0.0 {
+            arm.target_distance = (arm.target_distance - zoom * arm.zoom_step)
+                .clamp(arm.min_distance, arm.max_distance);
+        }
+        if swap {
+            arm.swap_shoulder();
+        }
+    }
+}
+
+/// Places the [`MainCamera`] at the end of its [`SpringArm`].
+fn follow_on_spring_arm(
+    time: Res<Time>,
+    spatial_query: SpatialQuery,
+    sensors: Query<(), With<Sensor>>,
+    mut main_camera: Single<(&mut Transform, &mut SpringArm), With<MainCamera>>,
+    main_character: Single<
+        (Entity, &Transform, Option<&UpDirection>),
+        (With<MainCharacter>, Without<MainCamera>),
+    >,
+) {
+    let (transform, arm) = &mut *main_camera;
+    let (character, character_transform, up) = *main_character;
+    let up = up.map_or(Dir3::Y, |up| up.0);
+    // Exponential smoothing, the same at any frame rate.
+    let blend = 1.0 - (-arm.smoothing * time.delta_secs()).exp();
+
+    let shoulder_target = arm.shoulder_side * arm.shoulder_offset;
+    arm.shoulder += (shoulder_target - arm.shoulder) * blend;
+    let pivot = character_transform.translation
+        + up * arm.pivot_height
+        + transform.right() * arm.shoulder;
+
+    // Shorten the arm to whatever is between the pivot and the camera.
+    let back = -transform.forward();
+    let clear_distance = spatial_query
+        .cast_shape_predicate(
+            &Collider::sphere(arm.probe_radius),
+            pivot,
+            Quat::IDENTITY,
+            back,
+            &ShapeCastConfig {
+                max_distance: arm.target_distance,
+                ..default()
+            },
+            &SpatialQueryFilter::from_excluded_entities([character]),
+            &|entity| !sensors.contains(entity),
+        )
+        .map_or(arm.target_distance, |hit| hit.distance);
+
+    arm.distance = if clear_distance < arm.distance {
+        clear_distance
+    } else {
+        arm.distance + (clear_distance - arm.distance) * blend
+    };
+    transform.translation = pivot + back * arm.distance;
+}
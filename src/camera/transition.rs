@@ -0,0 +1,61 @@
+//! Smooth blends of the [`MainCamera`] between [`CameraState`]s.
+//!
+//! On every state change the pose the camera had is remembered, and for a short while the camera
+//! is eased from it to wherever the new state places it.
+
+use bevy::prelude::*;
+
+use super::CameraSystems;
+use crate::simple_scene::game::{CameraState, MainCamera};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PostUpdate,
+        (
+            start_camera_transition.run_if(state_changed::<CameraState>),
+            blend_camera_transition,
+        )
+            .chain()
+            .in_set(CameraSystems::Blend),
+    );
+}
+
+/// How long blending into a new [`CameraState`] takes, in seconds.
+const TRANSITION_DURATION: f32 = 0.5;
+
+/// The pose the [`MainCamera`] is blending away from.
+#[derive(Component, Clone, Debug)]
+pub struct CameraTransition {
+    from: Transform,
+    elapsed: f32,
+}
+
+fn start_camera_transition(
+    mut commands: Commands,
+    main_camera: Single<(Entity, &GlobalTransform), With<MainCamera>>,
+) {
+    let (entity, global_transform) = *main_camera;
+    // The global transform still holds last frame's pose, before this frame's placement.
+    commands.entity(entity).insert(CameraTransition {
+        from: global_transform.compute_transform(),
+        elapsed: 0.0,
+    });
+}
+
+fn blend_camera_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    main_camera: Single<(Entity, &mut Transform, &mut CameraTransition), With<MainCamera>>,
+) {
+    let (entity, mut transform, mut transition) = main_camera.into_inner();
+    transition.elapsed += time.delta_secs();
+    let progress = (transition.elapsed / TRANSITION_DURATION).min(1.0);
+    let eased = progress * progress * (3.0 - 2.0 * progress);
+
+    transform.translation = transition.from.translation.lerp(transform.translation, eased);
+    transform.rotation = transition.from.rotation.slerp(transform.rotation, eased);
+
+    if progress >= 1.0 {
+        commands.entity(entity).remove::<CameraTransition>();
+    }
+}
@@ -0,0 +1,282 @@
+//! Shape-cast based collide-and-slide movement for kinematic character controllers.
+//!
+//! Instead of letting the physics step move the character and pushing it back out of whatever it
+//! ended up in, the move for the step is swept through the world with the character's collider.
+//! Every hit slides the rest of the move along the surface, low ledges are stepped onto, and the
+//! character is snapped back down to the ground when walking down slopes and stairs.
+
+use avian3d::{math::*, prelude::*};
+use bevy::{ecs::query::Has, prelude::*};
+
+use super::{CharacterController, Grounded, MaxSlopeAngle, UpDirection};
+use crate::time::ClockTime;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PhysicsSchedule,
+        (
+            record_slide_start.in_set(PhysicsStepSet::First),
+            // Run after the solver has integrated the velocity, so the swept move replaces it.
+            collide_and_slide.in_set(PhysicsStepSet::Last),
+        ),
+    );
+}
+
+/// Opts a character controller into collide-and-slide movement.
+///
+/// Entities without it keep the contact based collision response of
+/// `kinematic_controller_collisions`, so both can be compared side by side.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(SlideStart)]
+pub struct CollideAndSlide {
+    /// How many times the move may be deflected by a surface within one step.
+    pub max_iterations: usize,
+    /// The gap kept between the collider and the surfaces it touches.
+    pub skin_width: Scalar,
+    /// The highest ledge the character walks onto without jumping.
+    pub step_height: Scalar,
+    /// How far below the character the ground may drop away before it stops sticking to it.
+    pub snap_distance: Scalar,
+}
+
+impl Default for CollideAndSlide {
+    fn default() -> Self {
+        Self {
+            max_iterations: 4,
+            skin_width: 0.02,
+            step_height: 0.3,
+            snap_distance: 0.2,
+        }
+    }
+}
+
+/// The position of a [`CollideAndSlide`] character at the start of the physics step.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct SlideStart(pub Vector);
+
+/// Moves shorter than this are treated as no move at all.
+const MIN_MOVE: Scalar = 1e-4;
+
+pub(super) fn record_slide_start(mut controllers: Query<(&Position, &mut SlideStart)>) {
+    for (position, mut start) in &mut controllers {
+        start.0 = position.0;
+    }
+}
+
+/// Sweeps a collider through the world, ignoring the character itself and sensors.
+pub(super) struct Sweep<'a, 'w, 's> {
+    pub(super) spatial_query: &'a SpatialQuery<'w, 's>,
+    pub(super) collider: &'a Collider,
+    pub(super) rotation: Quaternion,
+    pub(super) filter: SpatialQueryFilter,
+    pub(super) sensors: &'a Query<'w, 's, (), With<Sensor>>,
+    pub(super) skin_width: Scalar,
+}
+
+impl Sweep<'_, '_, '_> {
+    /// Casts the collider from `origin` and returns the hit, stopping `skin_width` short of it.
+    fn cast(&self, origin: Vector, direction: Dir3, distance: Scalar) -> Option<ShapeHitData> {
+        self.spatial_query.cast_shape_predicate(
+            self.collider,
+            origin,
+            self.rotation,
+            direction,
+            &ShapeCastConfig {
+                max_distance: distance,
+                target_distance: self.skin_width,
+                ..default()
+            },
+            &self.filter,
+            &|entity| !self.sensors.contains(entity),
+        )
+    }
+
+    /// Moves from `origin` by `motion`, sliding along whatever is in the way for at most
+    /// `max_iterations` hits, and returns where the collider ends up.
+    pub(super) fn slide(&self, origin: Vector, motion: Vector, max_iterations: usize) -> Vector {
+        let mut translation = origin;
+        let mut motion = motion;
+        for _ in 0..max_iterations {
+            let Ok((direction, distance)) = Dir3::new_and_length(motion.f32()) else {
+                break;
+            };
+            let distance = distance.adjust_precision();
+            if distance < MIN_MOVE {
+                break;
+            }
+
+            let Some(hit) = self.cast(translation, direction, distance) else {
+                translation += motion;
+                break;
+            };
+            let travelled = direction.as_vec3().adjust_precision() * hit.distance;
+            translation += travelled;
+            motion = (motion - travelled).reject_from_normalized(hit.normal1);
+        }
+        translation
+    }
+
+    /// Tries to climb onto a ledge: up by at most `step_height`, forward along the part of
+    /// `motion` along the ground, then back down onto walkable ground.
+    fn step_up(
+        &self,
+        origin: Vector,
+        motion: Vector,
+        up: Dir3,
+        step_height: Scalar,
+        walkable: impl Fn(Vector) -> bool,
+    ) -> Option<Vector> {
+        let up_vector = up.as_vec3().adjust_precision();
+        let (forward, distance) =
+            Dir3::new_and_length(motion.reject_from_normalized(up_vector).f32()).ok()?;
+        let distance = distance.adjust_precision();
+
+        let rise = self
+            .cast(origin, up, step_height)
+            .map_or(step_height, |hit| hit.distance);
+        if rise < MIN_MOVE {
+            return None;
+        }
+        let raised = origin + up_vector * rise;
+
+        let advance = self
+            .cast(raised, forward, distance)
+            .map_or(distance, |hit| hit.distance);
+        if advance < MIN_MOVE {
+            return None;
+        }
+        let advanced = raised + forward.as_vec3().adjust_precision() * advance;
+
+        // The ledge has to be there and be walkable, otherwise this was just a wall.
+        let landing = self.cast(advanced, -up, rise)?;
+        walkable(landing.normal1).then(|| advanced - up_vector * landing.distance)
+    }
+}
+
+/// Replaces the move of the physics step with a swept collide-and-slide move.
+fn collide_and_slide(
+    clock: ClockTime,
+    spatial_query: SpatialQuery,
+    sensors: Query<(), With<Sensor>>,
+    mut controllers: Query<
+        (
+            Entity,
+            &CollideAndSlide,
+            &SlideStart,
+            &Collider,
+            &Rotation,
+            &mut Position,
+            &mut LinearVelocity,
+            &UpDirection,
+            Option<&MaxSlopeAngle>,
+            Has<Grounded>,
+        ),
+        With<CharacterController>,
+    >,
+) {
+    for (
+        entity,
+        settings,
+        start,
+        collider,
+        rotation,
+        mut position,
+        mut linear_velocity,
+        up_direction,
+        max_slope_angle,
+        is_grounded,
+    ) in &mut controllers
+    {
+        let delta_time = clock.physics_delta_secs_f64(entity).adjust_precision();
+        if delta_time <= 0.0 {
+            continue;
+        }
+        let up = up_direction.vector();
+        let sweep = Sweep {
+            spatial_query: &spatial_query,
+            collider,
+            rotation: rotation.0,
+            filter: SpatialQueryFilter::from_excluded_entities([entity]),
+            sensors: &sensors,
+            skin_width: settings.skin_width,
+        };
+        let walkable = |normal: Vector| {
+            max_slope_angle.is_some_and(|angle| normal.angle_between(up).abs() <= angle.0)
+        };
+
+        let mut translation = start.0;
+        let mut velocity = linear_velocity.0;
+        let mut motion = velocity * delta_time;
+        let mut touched_ground = false;
+
+        for _ in 0..settings.max_iterations {
+            let Ok((direction, distance)) = Dir3::new_and_length(motion.f32()) else {
+                break;
+            };
+            let distance = distance.adjust_precision();
+            if distance < MIN_MOVE {
+                break;
+            }
+
+            let Some(hit) = sweep.cast(translation, direction, distance) else {
+                translation += motion;
+                break;
+            };
+
+            let travelled = direction.as_vec3().adjust_precision() * hit.distance;
+            translation += travelled;
+            motion -= travelled;
+            let normal = hit.normal1;
+
+            if walkable(normal) {
+                touched_ground = true;
+                motion = motion.reject_from_normalized(normal);
+                if velocity.dot(normal) < 0.0 {
+                    velocity = velocity.reject_from_normalized(normal);
+                }
+                continue;
+            }
+
+            if is_grounded
+                && settings.step_height > 0.0
+                && let Some(stepped) = sweep.step_up(
+                    translation,
+                    motion,
+                    up_direction.0,
+                    settings.step_height,
+                    walkable,
+                )
+            {
+                translation = stepped;
+                touched_ground = true;
+                break;
+            }
+
+            // Treat steep surfaces as walls, so sliding along them never lifts the character.
+            let wall = normal.reject_from_normalized(up).normalize_or(normal);
+            for vector in [&mut motion, &mut velocity] {
+                if vector.dot(wall) < 0.0 {
+                    *vector = vector.reject_from_normalized(wall);
+                }
+                if vector.dot(normal) < 0.0 {
+                    *vector = vector.reject_from_normalized(normal);
+                }
+            }
+        }
+
+        // Stick to the ground when walking down slopes and stairs instead of flying off them.
+        if is_grounded
+            && !touched_ground
+            && velocity.dot(up) <= 0.0
+            && settings.snap_distance > 0.0
+            && let Some(hit) = sweep.cast(translation, -up_direction.0, settings.snap_distance)
+            && walkable(hit.normal1)
+        {
+            translation -= up * hit.distance;
+            velocity = velocity.reject_from_normalized(up);
+        }
+
+        position.0 = translation;
+        linear_velocity.0 = velocity;
+    }
+}
@@ -0,0 +1,235 @@
+//! Water, mud and other fluids the character controller can wade and swim through.
+//!
+//! A [`FluidVolume`] is a sensor that changes the medium for entities with [`ControllerGravity`]
+//! inside it. How deep they are in it, along their [`UpDirection`], is tracked in [`Submerged`]:
+//! the deeper, the more its buoyancy counters gravity and the more its drag slows them down. Deep
+//! enough in a swimmable fluid, character controllers are [`Swimming`] and move in 3D along their
+//! view instead of walking.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+use super::{CharacterController, ControllerGravity, JumpImpulse, MovementInput, UpDirection};
+use crate::simple_scene::game::{MainCamera, MainCharacter};
+use crate::time::ClockTime;
+
+/// A sensor volume filled with a fluid. All values are tunable per volume.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[require(Sensor)]
+pub struct FluidVolume {
+    /// The fraction of gravity buoyancy counters when fully submerged. Above 1, things float.
+    pub buoyancy: Scalar,
+    /// Scales gravity when fully submerged, before buoyancy.
+    pub gravity_scale: Scalar,
+    /// How quickly velocity decays when fully submerged, per second.
+    pub drag: Scalar,
+    /// The acceleration of swimming. Zero for fluids that can only be waded through.
+    pub swim_acceleration: Scalar,
+    /// How deep, as a fraction of the height of the collider, swimming starts.
+    pub swim_depth: Scalar,
+}
+
+impl FluidVolume {
+    pub const fn water() -> Self {
+        Self {
+            buoyancy: 1.05,
+            gravity_scale: 1.0,
+            drag: 1.5,
+            swim_acceleration: 12.0,
+            swim_depth: 0.6,
+        }
+    }
+
+    /// Thick enough to sink into slowly and too thick to swim in.
+    pub const fn mud() -> Self {
+        Self {
+            buoyancy: 0.7,
+            gravity_scale: 1.0,
+            drag: 6.0,
+            swim_acceleration: 0.0,
+            swim_depth: 1.0,
+        }
+    }
+}
+
+impl Default for FluidVolume {
+    fn default() -> Self {
+        Self::water()
+    }
+}
+
+/// How deep an entity with [`ControllerGravity`] is in a [`FluidVolume`].
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[component(storage = "SparseSet")]
+pub struct Submerged {
+    pub volume: Entity,
+    pub fluid: FluidVolume,
+    /// How much of the collider is below the surface, in `0..=1`.
+    pub fraction: Scalar,
+}
+
+impl Submerged {
+    /// The scale of [`ControllerGravity`] with buoyancy taken into account.
+    pub fn gravity_scale(&self) -> Scalar {
+        1.0 - self.fraction + self.fraction * (self.fluid.gravity_scale - self.fluid.buoyancy)
+    }
+}
+
+/// A marker component indicating that a character controller is swimming.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Swimming;
+
+/// The lowest and highest points along `up` of a box around `center`, turned by `rotation`.
+fn extent_along(
+    center: Vector,
+    half_extents: Vector,
+    rotation: Quaternion,
+    up: Vector,
+) -> (Scalar, Scalar) {
+    let local_up = rotation.inverse() * up;
+    let center = center.dot(up);
+    let half_extent = half_extents.dot(local_up.abs());
+    (center - half_extent, center + half_extent)
+}
+
+/// Updates [`Submerged`] and [`Swimming`] from the [`FluidVolume`]s entities are in.
+pub(super) fn update_submersion(
+    mut commands: Commands,
+    spatial_query: SpatialQuery,
+    volumes: Query<(&FluidVolume, &ColliderAabb)>,
+    bodies: Query<
+        (
+            Entity,
+            &Collider,
+            &Position,
+            &Rotation,
+            Option<&UpDirection>,
+        ),
+        With<ControllerGravity>,
+    >,
+) {
+    for (entity, collider, position, rotation, up) in &bodies {
+        let up = up.copied().unwrap_or_default().vector();
+        let local_aabb = collider.aabb(Vector::ZERO, Rotation::default());
+        let (bottom, top) = extent_along(
+            position.0 + rotation.0 * (local_aabb.min + local_aabb.max) / 2.0,
+            (local_aabb.max - local_aabb.min) / 2.0,
+            rotation.0,
+            up,
+        );
+        let height = (top - bottom).max(Scalar::EPSILON);
+
+        let deepest = spatial_query
+            .shape_intersections(
+                collider,
+                position.0,
+                rotation.0,
+                &SpatialQueryFilter::from_excluded_entities([entity]),
+            )
+            .into_iter()
+            .filter_map(|volume| {
+                let (fluid, volume_aabb) = volumes.get(volume).ok()?;
+                let (_, surface) = extent_along(
+                    (volume_aabb.min + volume_aabb.max) / 2.0,
+                    (volume_aabb.max - volume_aabb.min) / 2.0,
+                    Quaternion::IDENTITY,
+                    up,
+                );
+                let fraction = ((surface - bottom) / height).clamp(0.0, 1.0);
+                Some(Submerged {
+                    volume,
+                    fluid: *fluid,
+                    fraction,
+                })
+            })
+            .max_by(|a, b| a.fraction.total_cmp(&b.fraction));
+
+        let mut entity_commands = commands.entity(entity);
+        match deepest {
+            Some(submerged) => {
+                let swimming = submerged.fluid.swim_acceleration > 0.0
+                    && submerged.fraction >= submerged.fluid.swim_depth;
+                entity_commands.insert(submerged);
+                if swimming {
+                    entity_commands.insert(Swimming);
+                } else {
+                    entity_commands.remove::<Swimming>();
+                }
+            }
+            None => {
+                entity_commands.remove::<(Submerged, Swimming)>();
+            }
+        }
+    }
+}
+
+/// Moves [`Swimming`] character controllers by their [`MovementInput`], along the view of the
+/// [`MainCamera`] for the [`MainCharacter`] and along the way they face for others. Holding jump
+/// swims up and crouch swims down, and jump pressed at the surface jumps out of the fluid.
+pub(super) fn swim(
+    clock: ClockTime,
+    main_camera: Query<&Transform, With<MainCamera>>,
+    mut controllers: Query<
+        (
+            Entity,
+            &mut MovementInput,
+            &Submerged,
+            &UpDirection,
+            &Rotation,
+            Option<&JumpImpulse>,
+            &mut LinearVelocity,
+            Has<MainCharacter>,
+        ),
+        (With<Swimming>, With<CharacterController>),
+    >,
+) {
+    let camera_rotation = main_camera
+        .single()
+        .ok()
+        .map(|transform| transform.rotation);
+
+    for (
+        entity,
+        mut input,
+        submerged,
+        up,
+        rotation,
+        jump_impulse,
+        mut linear_velocity,
+        is_main_character,
+    ) in &mut controllers
+    {
+        let delta_time = clock.delta_secs_f64(entity).adjust_precision();
+        let up = up.vector();
+        let view = camera_rotation
+            .filter(|_| is_main_character)
+            .unwrap_or(rotation.0);
+        let vertical = input.jump_held as i8 as Scalar - input.crouch_held as i8 as Scalar;
+        let direction = (view * Vec3::new(input.direction.x, 0.0, -input.direction.y))
+            .adjust_precision()
+            + up * vertical;
+        linear_velocity.0 +=
+            direction.clamp_length_max(1.0) * submerged.fluid.swim_acceleration * delta_time;
+
+        // Only the head is out of the fluid, so there is something to push off of.
+        if std::mem::take(&mut input.jump_pressed)
+            && submerged.fraction < 1.0
+            && let Some(jump_impulse) = jump_impulse
+        {
+            let up_speed = linear_velocity.dot(up);
+            linear_velocity.0 += up * (up_speed.max(0.0) + jump_impulse.0 - up_speed);
+        }
+    }
+}
+
+/// Slows down entities in fluids, the more the deeper they are.
+pub(super) fn apply_fluid_drag(
+    clock: ClockTime,
+    mut bodies: Query<(Entity, &Submerged, &mut LinearVelocity)>,
+) {
+    for (entity, submerged, mut linear_velocity) in &mut bodies {
+        let delta_time = clock.delta_secs_f64(entity).adjust_precision();
+        linear_velocity.0 *= (-submerged.fluid.drag * submerged.fraction * delta_time).exp();
+    }
+}
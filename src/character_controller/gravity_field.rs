@@ -0,0 +1,156 @@
+//! Gravity zones that change where down is.
+//!
+//! Inside a [`GravityField`] sensor, the [`ControllerGravity`] of a character controller comes
+//! from the field instead of its [`DefaultGravity`]. Its [`UpDirection`] turns to face away from
+//! gravity, and the character turns with it, so slope checks, movement and the camera all follow
+//! the local up.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+use super::{CharacterController, ControllerGravity};
+use crate::time::ClockTime;
+
+/// How fast the [`UpDirection`] turns towards a new gravity, in radians per second.
+const UP_TURN_SPEED: Scalar = 4.0;
+
+/// The shape of a [`GravityField`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GravityFieldKind {
+    /// Pulls in one direction, given in the local space of the field.
+    Directional(Dir3),
+    /// Pulls towards the center of the field, like a planet.
+    Spherical,
+    /// Pulls towards an axis through the center of the field, given in its local space.
+    Cylindrical { axis: Dir3 },
+}
+
+/// A sensor volume that overrides the gravity of character controllers inside it.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[require(Sensor)]
+pub struct GravityField {
+    pub kind: GravityFieldKind,
+    /// Gravitational acceleration, in meters per second squared.
+    pub strength: Scalar,
+    /// Where fields overlap, the one with the highest priority wins.
+    pub priority: i32,
+}
+
+impl GravityField {
+    pub const fn directional(direction: Dir3, strength: Scalar) -> Self {
+        Self {
+            kind: GravityFieldKind::Directional(direction),
+            strength,
+            priority: 0,
+        }
+    }
+
+    pub const fn spherical(strength: Scalar) -> Self {
+        Self {
+            kind: GravityFieldKind::Spherical,
+            strength,
+            priority: 0,
+        }
+    }
+
+    pub const fn cylindrical(axis: Dir3, strength: Scalar) -> Self {
+        Self {
+            kind: GravityFieldKind::Cylindrical { axis },
+            strength,
+            priority: 0,
+        }
+    }
+
+    pub const fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// The gravity at `point` for a field at `center` with `rotation`.
+    pub fn gravity_at(&self, center: Vector, rotation: Quaternion, point: Vector) -> Vector {
+        let direction = match self.kind {
+            GravityFieldKind::Directional(direction) => {
+                rotation * direction.as_vec3().adjust_precision()
+            }
+            GravityFieldKind::Spherical => (center - point).normalize_or_zero(),
+            GravityFieldKind::Cylindrical { axis } => {
+                let axis = rotation * axis.as_vec3().adjust_precision();
+                -(point - center).reject_from_normalized(axis).normalize_or_zero()
+            }
+        };
+        direction * self.strength
+    }
+}
+
+/// The gravity of a character controller outside of [`GravityField`]s.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DefaultGravity(pub Vector);
+
+/// The direction a character controller considers up.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct UpDirection(pub Dir3);
+
+impl Default for UpDirection {
+    fn default() -> Self {
+        Self(Dir3::Y)
+    }
+}
+
+impl UpDirection {
+    pub fn vector(&self) -> Vector {
+        self.0.as_vec3().adjust_precision()
+    }
+}
+
+/// The gravity at `point` from the [`GravityField`] with the highest priority there, if any.
+pub fn field_gravity_at(
+    spatial_query: &SpatialQuery,
+    fields: &Query<(&GravityField, &Position, &Rotation)>,
+    point: Vector,
+) -> Option<Vector> {
+    spatial_query
+        .point_intersections(point, &SpatialQueryFilter::default())
+        .into_iter()
+        .filter_map(|entity| fields.get(entity).ok())
+        .max_by_key(|(field, ..)| field.priority)
+        .map(|(field, center, rotation)| field.gravity_at(center.0, rotation.0, point))
+}
+
+/// Sets the [`ControllerGravity`] of character controllers from the [`GravityField`] they are in,
+/// and turns their [`UpDirection`] and rotation to match.
+pub(super) fn apply_gravity_fields(
+    clock: ClockTime,
+    spatial_query: SpatialQuery,
+    fields: Query<(&GravityField, &Position, &Rotation)>,
+    mut controllers: Query<
+        (
+            Entity,
+            &Position,
+            &DefaultGravity,
+            &mut ControllerGravity,
+            &mut UpDirection,
+            &mut Rotation,
+        ),
+        (With<CharacterController>, Without<GravityField>),
+    >,
+) {
+    for (entity, position, default_gravity, mut gravity, mut up, mut rotation) in &mut controllers {
+        let delta_time = clock.delta_secs_f64(entity).adjust_precision();
+        gravity.0 =
+            field_gravity_at(&spatial_query, &fields, position.0).unwrap_or(default_gravity.0);
+
+        // Without gravity, there's no reason to change what's up.
+        let Ok(target_up) = Dir3::new(-gravity.0.f32()) else {
+            continue;
+        };
+
+        let angle = up.0.angle_between(*target_up).adjust_precision();
+        if angle <= Scalar::EPSILON {
+            continue;
+        }
+        let progress = (UP_TURN_SPEED * delta_time / angle).min(1.0);
+        let turn = Quat::IDENTITY.slerp(Quat::from_rotation_arc(*up.0, *target_up), progress.f32());
+        up.0 = turn * up.0;
+        rotation.0 = (turn.adjust_precision() * rotation.0).normalize();
+    }
+}
@@ -8,7 +8,31 @@ use avian3d::{
     prelude::{NarrowPhaseSet, *},
 };
 use bevy::{ecs::query::Has, prelude::*};
-use crate::simple_scene::game::{CameraState, MainCamera, MainCharacter};
+use crate::input::{ActionState, GameAction, GameAxis};
+use crate::simple_scene::game::{MainCamera, MainCharacter, character_controlled};
+use crate::time::ClockTime;
+
+mod collide_and_slide;
+mod fluid;
+mod gravity_field;
+mod platform;
+mod push;
+mod safe_spawn;
+mod stance;
+#[cfg(test)]
+mod tests;
+mod traversal;
+
+pub use collide_and_slide::{CollideAndSlide, SlideStart};
+pub use fluid::{FluidVolume, Submerged, Swimming};
+pub use gravity_field::{
+    DefaultGravity, GravityField, GravityFieldKind, UpDirection, field_gravity_at,
+};
+pub use platform::{GroundedOn, PlatformVelocity};
+pub use push::{Knockback, PushStrength};
+pub use safe_spawn::{find_safe_position, is_penetrating};
+pub use stance::{MovementSpeedScale, Sprint, Stance, StanceShape, Stances};
+pub use traversal::{Climber, Ladder, Ledge, TraversalMode};
 
 pub struct CharacterControllerPlugin;
 
@@ -18,12 +42,26 @@ impl Plugin for CharacterControllerPlugin {
             .add_systems(
                 Update,
                 (
-                    keyboard_input.run_if(in_state(CameraState::FirstPersonView)),
-                    gamepad_input.run_if(in_state(CameraState::FirstPersonView)),
+                    action_input.run_if(character_controlled),
+                    collect_movement_actions,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                // Run on the fixed timestep right before physics, so movement feels the same at
+                // any frame rate. `TransformInterpolation` smooths out the rendered motion.
+                FixedUpdate,
+                (
+                    gravity_field::apply_gravity_fields,
                     update_grounded,
+                    stance::update_stance,
+                    traversal::traverse,
+                    fluid::update_submersion,
                     apply_gravity,
-                    movement.run_if(in_state(CameraState::FirstPersonView)),
+                    movement.run_if(character_controlled),
+                    fluid::swim,
                     apply_movement_damping,
+                    fluid::apply_fluid_drag,
                 )
                     .chain(),
             )
@@ -31,10 +69,12 @@ impl Plugin for CharacterControllerPlugin {
                 // Run collision handling after collision detection.
                 //
                 // NOTE: The collision implementation here is very basic and a bit buggy.
-                //       A collide-and-slide algorithm would likely work better.
+                //       Controllers with `CollideAndSlide` use a shape-cast based
+                //       collide-and-slide move instead.
                 PhysicsSchedule,
                 kinematic_controller_collisions.in_set(NarrowPhaseSet::Last),
-            );
+            )
+            .add_plugins((collide_and_slide::plugin, platform::plugin, push::plugin));
     }
 }
 
@@ -42,13 +82,36 @@ impl Plugin for CharacterControllerPlugin {
 #[derive(Event)]
 pub enum MovementAction {
     Move(Vector2),
+    /// Sent on the frame jump is pressed.
     Jump,
+    /// Sent on every frame jump is held, to jump higher.
+    JumpHeld,
+    /// Sent on every frame crouch is held.
+    Crouch,
+    /// Sent on the frame prone is pressed, to lie down or get back up.
+    Prone,
+    /// Sent on every frame sprint is held.
+    Sprint,
 }
 
 /// A marker component indicating that an entity is using a character controller.
 #[derive(Component)]
+#[require(PlatformVelocity, MovementInput, UpDirection)]
 pub struct CharacterController;
 
+/// The [`MovementAction`]s of the last frame, held until the next fixed update consumes them.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct MovementInput {
+    pub direction: Vector2,
+    /// Stays set until a fixed update has seen it, so presses between fixed steps aren't lost.
+    pub jump_pressed: bool,
+    pub jump_held: bool,
+    pub crouch_held: bool,
+    /// Toggled by [`MovementAction::Prone`].
+    pub prone: bool,
+    pub sprint_held: bool,
+}
+
 /// A marker component indicating that an entity is on the ground.
 #[derive(Component)]
 #[component(storage = "SparseSet")]
@@ -58,14 +121,57 @@ pub struct Grounded;
 #[derive(Component)]
 pub struct MovementAcceleration(Scalar);
 
-/// The damping factor used for slowing down movement.
+/// The damping factor used for slowing down movement, as the fraction of horizontal velocity kept
+/// every 1/60th of a second.
 #[derive(Component)]
 pub struct MovementDampingFactor(Scalar);
 
+/// The acceleration used for character movement while in the air.
+#[derive(Component)]
+pub struct AirAcceleration(Scalar);
+
+/// The damping factor used for slowing down movement while in the air, like
+/// [`MovementDampingFactor`].
+#[derive(Component)]
+pub struct AirDampingFactor(Scalar);
+
 /// The strength of a jump.
 #[derive(Component)]
 pub struct JumpImpulse(Scalar);
 
+/// How long after walking off a ledge the character can still jump, in seconds.
+#[derive(Component)]
+pub struct CoyoteTime(Scalar);
+
+/// How long a jump pressed before landing is remembered, in seconds.
+#[derive(Component)]
+pub struct JumpBuffer(Scalar);
+
+/// Holding jump right after jumping scales gravity down, so the character jumps higher.
+#[derive(Component)]
+pub struct JumpHold {
+    /// How long after the jump holding still has an effect, in seconds.
+    duration: Scalar,
+    /// The gravity scale while holding.
+    gravity_scale: Scalar,
+}
+
+/// How many more times the character can jump before landing again, such as for a double jump.
+#[derive(Component)]
+pub struct AirJumps(u32);
+
+/// The timers of the jump model, kept up to date while the character moves.
+#[derive(Component, Default)]
+pub struct JumpState {
+    /// Time since the character was last grounded.
+    since_grounded: Scalar,
+    /// Time left for a buffered jump to trigger.
+    buffered: Option<Scalar>,
+    /// Time since the jump, while jump is still held.
+    holding: Option<Scalar>,
+    air_jumps_used: u32,
+}
+
 /// The gravitational acceleration used for a character controller.
 #[derive(Component)]
 pub struct ControllerGravity(Vector);
@@ -85,6 +191,7 @@ pub struct CharacterControllerBundle {
     collider: Collider,
     ground_caster: ShapeCaster,
     gravity: ControllerGravity,
+    default_gravity: DefaultGravity,
     movement: MovementBundle,
 }
 
@@ -93,7 +200,14 @@ pub struct CharacterControllerBundle {
 pub struct MovementBundle {
     acceleration: MovementAcceleration,
     damping: MovementDampingFactor,
+    air_acceleration: AirAcceleration,
+    air_damping: AirDampingFactor,
     jump_impulse: JumpImpulse,
+    coyote_time: CoyoteTime,
+    jump_buffer: JumpBuffer,
+    jump_hold: JumpHold,
+    air_jumps: AirJumps,
+    jump_state: JumpState,
     max_slope_angle: MaxSlopeAngle,
 }
 
@@ -107,10 +221,56 @@ impl MovementBundle {
         Self {
             acceleration: MovementAcceleration(acceleration),
             damping: MovementDampingFactor(damping),
+            air_acceleration: AirAcceleration(acceleration * 0.3),
+            air_damping: AirDampingFactor(0.99),
             jump_impulse: JumpImpulse(jump_impulse),
+            coyote_time: CoyoteTime(0.1),
+            jump_buffer: JumpBuffer(0.12),
+            jump_hold: JumpHold {
+                duration: 0.2,
+                gravity_scale: 0.5,
+            },
+            air_jumps: AirJumps(0),
+            jump_state: JumpState {
+                since_grounded: 0.0,
+                buffered: None,
+                holding: None,
+                air_jumps_used: 0,
+            },
             max_slope_angle: MaxSlopeAngle(max_slope_angle),
         }
     }
+
+    /// Sets the acceleration and damping factor used in the air.
+    pub const fn with_air_control(mut self, acceleration: Scalar, damping: Scalar) -> Self {
+        self.air_acceleration = AirAcceleration(acceleration);
+        self.air_damping = AirDampingFactor(damping);
+        self
+    }
+
+    /// Sets the coyote time and jump buffer, in seconds.
+    pub const fn with_jump_timing(mut self, coyote_time: Scalar, jump_buffer: Scalar) -> Self {
+        self.coyote_time = CoyoteTime(coyote_time);
+        self.jump_buffer = JumpBuffer(jump_buffer);
+        self
+    }
+
+    /// Sets how long holding jump scales gravity, and by how much.
+    ///
+    /// A `gravity_scale` of 1 makes every jump equally high.
+    pub const fn with_jump_hold(mut self, duration: Scalar, gravity_scale: Scalar) -> Self {
+        self.jump_hold = JumpHold {
+            duration,
+            gravity_scale,
+        };
+        self
+    }
+
+    /// Sets how many jumps are possible in the air, 1 for a double jump.
+    pub const fn with_air_jumps(mut self, air_jumps: u32) -> Self {
+        self.air_jumps = AirJumps(air_jumps);
+        self
+    }
 }
 
 impl Default for MovementBundle {
@@ -137,6 +297,7 @@ impl CharacterControllerBundle {
             )
             .with_max_distance(0.2),
             gravity: ControllerGravity(gravity),
+            default_gravity: DefaultGravity(gravity),
             movement: MovementBundle::default(),
         }
     }
@@ -151,140 +312,295 @@ impl CharacterControllerBundle {
         self.movement = MovementBundle::new(acceleration, damping, jump_impulse, max_slope_angle);
         self
     }
+
+    /// Replaces the movement components, for tuning beyond [`with_movement`](Self::with_movement).
+    pub fn with_movement_bundle(mut self, movement: MovementBundle) -> Self {
+        self.movement = movement;
+        self
+    }
 }
 
-/// Sends [`MovementAction`] events based on keyboard input.
-fn keyboard_input(
+/// Sends [`MovementAction`] events based on the [`ActionState`] of the move axes and jump.
+fn action_input(
     mut movement_event_writer: EventWriter<MovementAction>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    action_state: Res<ActionState>,
 ) {
-    let up = keyboard_input.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]);
-    let down = keyboard_input.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]);
-    let left = keyboard_input.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]);
-    let right = keyboard_input.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]);
-
-    let horizontal = right as i8 - left as i8;
-    let vertical = up as i8 - down as i8;
-    let direction = Vector2::new(horizontal as Scalar, vertical as Scalar).clamp_length_max(1.0);
+    let direction = action_state
+        .axis_pair(GameAxis::MoveX, GameAxis::MoveY)
+        .adjust_precision();
 
     if direction != Vector2::ZERO {
         movement_event_writer.write(MovementAction::Move(direction));
     }
 
-    if keyboard_input.just_pressed(KeyCode::Space) {
+    if action_state.just_pressed(GameAction::Jump) {
         movement_event_writer.write(MovementAction::Jump);
     }
-}
 
-/// Sends [`MovementAction`] events based on gamepad input.
-fn gamepad_input(
-    mut movement_event_writer: EventWriter<MovementAction>,
-    gamepads: Query<&Gamepad>,
-) {
-    for gamepad in gamepads.iter() {
-        if let (Some(x), Some(y)) = (
-            gamepad.get(GamepadAxis::LeftStickX),
-            gamepad.get(GamepadAxis::LeftStickY),
-        ) {
-            movement_event_writer.write(MovementAction::Move(
-                Vector2::new(x as Scalar, y as Scalar).clamp_length_max(1.0),
-            ));
-        }
+    if action_state.pressed(GameAction::Jump) {
+        movement_event_writer.write(MovementAction::JumpHeld);
+    }
 
-        if gamepad.just_pressed(GamepadButton::South) {
-            movement_event_writer.write(MovementAction::Jump);
-        }
+    if action_state.pressed(GameAction::Crouch) {
+        movement_event_writer.write(MovementAction::Crouch);
+    }
+
+    if action_state.just_pressed(GameAction::Prone) {
+        movement_event_writer.write(MovementAction::Prone);
+    }
+
+    if action_state.pressed(GameAction::Sprint) {
+        movement_event_writer.write(MovementAction::Sprint);
     }
 }
 
-/// Updates the [`Grounded`] status for character controllers.
+/// Updates the [`Grounded`] status for character controllers, and what they are [`GroundedOn`].
+///
+/// A character that just jumped isn't grounded while it is still rising off the ground, or its
+/// coyote time and air jumps would be reset by the ground it is leaving.
 fn update_grounded(
     mut commands: Commands,
     mut query: Query<
-        (Entity, &ShapeHits, &Rotation, Option<&MaxSlopeAngle>),
+        (
+            Entity,
+            &ShapeHits,
+            &Rotation,
+            &UpDirection,
+            &LinearVelocity,
+            Option<&JumpState>,
+            Option<&MaxSlopeAngle>,
+        ),
         With<CharacterController>,
     >,
+    collider_rbs: Query<&ColliderOf>,
 ) {
-    for (entity, hits, rotation, max_slope_angle) in &mut query {
+    for (entity, hits, rotation, up, linear_velocity, jump_state, max_slope_angle) in &mut query {
+        let jumping = jump_state.is_some_and(|jump_state| jump_state.holding.is_some());
+
         // The character is grounded if the shape caster has a hit with a normal
-        // that isn't too steep.
-        let is_grounded = hits.iter().any(|hit| {
-            if let Some(angle) = max_slope_angle {
-                (rotation * -hit.normal2).angle_between(Vector::Y).abs() <= angle.0
-            } else {
-                true
-            }
+        // that isn't too steep, and it isn't moving away from it.
+        let ground = hits.iter().find(|hit| {
+            let normal = rotation * -hit.normal2;
+            let walkable = max_slope_angle
+                .is_none_or(|angle| normal.angle_between(up.vector()).abs() <= angle.0);
+            let leaving = jumping || linear_velocity.dot(normal) > MAX_GROUNDED_SEPARATION_SPEED;
+            walkable && !leaving
         });
 
-        if is_grounded {
-            commands.entity(entity).insert(Grounded);
+        if let Some(hit) = ground {
+            // The hit is a collider, the platform velocity lives on its rigid body.
+            let body = collider_rbs.get(hit.entity).map_or(hit.entity, |collider| collider.body);
+            commands.entity(entity).insert((Grounded, GroundedOn(body)));
         } else {
-            commands.entity(entity).remove::<Grounded>();
+            commands.entity(entity).remove::<(Grounded, GroundedOn)>();
         }
     }
 }
 
-/// Responds to [`MovementAction`] events and moves character controllers accordingly.
-fn movement(
-    time: Res<Time>,
+/// How fast a character can move away from the ground and still count as standing on it.
+const MAX_GROUNDED_SEPARATION_SPEED: Scalar = 0.5;
+
+/// Stores the [`MovementAction`] events of this frame in the [`MovementInput`] of the
+/// [`MainCharacter`].
+fn collect_movement_actions(
     mut movement_event_reader: EventReader<MovementAction>,
+    mut controllers: Query<&mut MovementInput, With<MainCharacter>>,
+) {
+    let mut direction = Vector2::ZERO;
+    let mut jump_pressed = false;
+    let mut jump_held = false;
+    let mut crouch_held = false;
+    let mut prone_pressed = false;
+    let mut sprint_held = false;
+    for event in movement_event_reader.read() {
+        match event {
+            MovementAction::Move(event_direction) => direction += *event_direction,
+            MovementAction::Jump => jump_pressed = true,
+            MovementAction::JumpHeld => jump_held = true,
+            MovementAction::Crouch => crouch_held = true,
+            MovementAction::Prone => prone_pressed = true,
+            MovementAction::Sprint => sprint_held = true,
+        }
+    }
+
+    for mut input in &mut controllers {
+        input.direction = direction;
+        input.jump_pressed |= jump_pressed;
+        input.jump_held = jump_held;
+        input.crouch_held = crouch_held;
+        input.prone ^= prone_pressed;
+        input.sprint_held = sprint_held;
+    }
+}
+
+/// Moves character controllers according to their [`MovementInput`].
+fn movement(
+    clock: ClockTime,
     mut controllers: Query<(
+        Entity,
+        &mut MovementInput,
         &MovementAcceleration,
+        &AirAcceleration,
         &JumpImpulse,
+        &CoyoteTime,
+        &JumpBuffer,
+        &JumpHold,
+        &AirJumps,
+        &mut JumpState,
         &mut LinearVelocity,
+        &UpDirection,
+        Option<&MovementSpeedScale>,
         Has<Grounded>,
-    ), With<MainCharacter>>,
+    ), (With<MainCharacter>, Without<TraversalMode>, Without<Swimming>)>,
     main_camera: Single<&Transform, With<MainCamera>>
 ) {
 
     let camera_transform = main_camera.into_inner();
-    // Precision is adjusted so that the example works with
-    // both the `f32` and `f64` features. Otherwise you don't need this.
-    let delta_time = time.delta_secs_f64().adjust_precision();
 
-    for event in movement_event_reader.read() {
-        for (movement_acceleration, jump_impulse, mut linear_velocity, is_grounded) in
-            &mut controllers
-        {
-            match event {
-                MovementAction::Move(direction) => {
-                    // Convert input direction to local space
-                    let local_dir = camera_transform.rotation * Vec3::new(direction.x, 0.0, -direction.y);
+    for (
+        entity,
+        mut input,
+        movement_acceleration,
+        air_acceleration,
+        jump_impulse,
+        coyote_time,
+        jump_buffer,
+        jump_hold,
+        air_jumps,
+        mut jump_state,
+        mut linear_velocity,
+        up,
+        speed_scale,
+        is_grounded,
+    ) in &mut controllers
+    {
+        // Precision is adjusted so that the example works with
+        // both the `f32` and `f64` features. Otherwise you don't need this.
+        let delta_time = clock.delta_secs_f64(entity).adjust_precision();
+        let direction = input.direction;
+        let jump_pressed = std::mem::take(&mut input.jump_pressed);
+        let jump_held = input.jump_held;
+        let up = up.vector();
+
+        if direction != Vector2::ZERO {
+            // Convert input direction to local space
+            let local_dir = camera_transform.rotation * Vec3::new(direction.x, 0.0, -direction.y);
+            let acceleration = if is_grounded {
+                movement_acceleration.0
+            } else {
+                air_acceleration.0
+            } * speed_scale.map_or(1.0, |scale| scale.0);
 
-                    linear_velocity.x += local_dir.x * movement_acceleration.0 * delta_time;
-                    linear_velocity.z += local_dir.z * movement_acceleration.0 * delta_time;
+            // Only move along the ground, whichever way it faces.
+            linear_velocity.0 +=
+                local_dir.adjust_precision().reject_from_normalized(up) * acceleration * delta_time;
+        }
 
-                }
-                MovementAction::Jump => {
-                    if is_grounded {
-                        linear_velocity.y += jump_impulse.0;
-                    }
-                }
+        if is_grounded {
+            jump_state.since_grounded = 0.0;
+            jump_state.air_jumps_used = 0;
+        } else {
+            jump_state.since_grounded += delta_time;
+        }
+
+        jump_state.buffered = if jump_pressed {
+            Some(jump_buffer.0)
+        } else {
+            jump_state
+                .buffered
+                .map(|left| left - delta_time)
+                .filter(|left| *left >= 0.0)
+        };
+
+        // Jumping lifts the character off the ground, holding keeps gravity low for a while.
+        jump_state.holding = jump_state.holding.map(|held| held + delta_time).filter(|held| {
+            jump_held && *held <= jump_hold.duration && linear_velocity.dot(up) > 0.0
+        });
+
+        if jump_state.buffered.is_none() {
+            continue;
+        }
+        let on_ground = jump_state.since_grounded <= coyote_time.0;
+        if !on_ground {
+            // Only a fresh press triggers an air jump, a buffered one waits for the landing.
+            if !jump_pressed || jump_state.air_jumps_used >= air_jumps.0 {
+                continue;
             }
+            jump_state.air_jumps_used += 1;
         }
+
+        // Falling speed doesn't eat into jumps made in coyote time or in the air.
+        let up_speed = linear_velocity.dot(up);
+        linear_velocity.0 += up * (up_speed.max(0.0) + jump_impulse.0 - up_speed);
+        jump_state.buffered = None;
+        jump_state.holding = Some(0.0);
+        // Coyote time is used up, the next ground jump needs a landing first.
+        jump_state.since_grounded = Scalar::INFINITY;
     }
 }
 
-/// Applies [`ControllerGravity`] to character controllers.
+/// Applies [`ControllerGravity`] to character controllers that aren't in a [`TraversalMode`],
+/// taking the fluid they are [`Submerged`] in into account.
 fn apply_gravity(
-    time: Res<Time>,
-    mut controllers: Query<(&ControllerGravity, &mut LinearVelocity)>,
+    clock: ClockTime,
+    mut controllers: Query<
+        (
+            Entity,
+            &ControllerGravity,
+            &mut LinearVelocity,
+            Option<&JumpState>,
+            Option<&JumpHold>,
+            Option<&Submerged>,
+        ),
+        Without<TraversalMode>,
+    >,
 ) {
-    // Precision is adjusted so that the example works with
-    // both the `f32` and `f64` features. Otherwise you don't need this.
-    let delta_time = time.delta_secs_f64().adjust_precision();
-
-    for (gravity, mut linear_velocity) in &mut controllers {
-        linear_velocity.0 += gravity.0 * delta_time;
+    for (entity, gravity, mut linear_velocity, jump_state, jump_hold, submerged) in &mut controllers
+    {
+        // Precision is adjusted so that the example works with
+        // both the `f32` and `f64` features. Otherwise you don't need this.
+        let delta_time = clock.delta_secs_f64(entity).adjust_precision();
+        let scale = match (jump_state, jump_hold) {
+            (Some(JumpState { holding: Some(_), .. }), Some(jump_hold)) => jump_hold.gravity_scale,
+            _ => 1.0,
+        } * submerged.map_or(1.0, Submerged::gravity_scale);
+        linear_velocity.0 += gravity.0 * scale * delta_time;
     }
 }
 
-/// Slows down movement in the XZ plane.
-fn apply_movement_damping(mut query: Query<(&MovementDampingFactor, &mut LinearVelocity)>) {
-    for (damping_factor, mut linear_velocity) in &mut query {
-        // We could use `LinearDamping`, but we don't want to dampen movement along the Y axis
-        linear_velocity.x *= damping_factor.0;
-        linear_velocity.z *= damping_factor.0;
+/// The rate the damping factors are given for.
+const DAMPING_REFERENCE_RATE: Scalar = 60.0;
+
+/// Slows down movement along the ground, less so in the air if there is an [`AirDampingFactor`].
+///
+/// The damping decays velocity exponentially over time, so it doesn't depend on the time step.
+fn apply_movement_damping(
+    clock: ClockTime,
+    mut query: Query<
+        (
+            Entity,
+            &MovementDampingFactor,
+            Option<&AirDampingFactor>,
+            &mut LinearVelocity,
+            Option<&UpDirection>,
+            Has<Grounded>,
+        ),
+        Without<TraversalMode>,
+    >,
+) {
+    for (entity, damping_factor, air_damping_factor, mut linear_velocity, up, is_grounded) in
+        &mut query
+    {
+        let delta_time = clock.delta_secs_f64(entity).adjust_precision();
+        let damping_factor = match air_damping_factor {
+            Some(air_damping_factor) if !is_grounded => air_damping_factor.0,
+            _ => damping_factor.0,
+        }
+        .powf(delta_time * DAMPING_REFERENCE_RATE);
+        // We could use `LinearDamping`, but we don't want to dampen movement along the up axis
+        let up = up.map_or(Vector::Y, UpDirection::vector);
+        let vertical = up * linear_velocity.dot(up);
+        linear_velocity.0 = vertical + (linear_velocity.0 - vertical) * damping_factor;
     }
 }
 
@@ -295,14 +611,17 @@ fn apply_movement_damping(mut query: Query<(&MovementDampingFactor, &mut LinearV
 /// by pushing them along their contact normals by the current penetration depth,
 /// and applying velocity corrections in order to snap to slopes, slide along walls,
 /// and predict collisions using speculative contacts.
-#[allow(clippy::type_complexity)]
 fn kinematic_controller_collisions(
     collisions: Collisions,
     bodies: Query<&RigidBody>,
     collider_rbs: Query<&ColliderOf, Without<Sensor>>,
     mut character_controllers: Query<
-        (&mut Position, &mut LinearVelocity, Option<&MaxSlopeAngle>),
-        (With<RigidBody>, With<CharacterController>),
+        (&mut Position, &mut LinearVelocity, &UpDirection, Option<&MaxSlopeAngle>),
+        (
+            With<RigidBody>,
+            With<CharacterController>,
+            Without<CollideAndSlide>,
+        ),
     >,
     time: Res<Time>,
 ) {
@@ -322,7 +641,7 @@ fn kinematic_controller_collisions(
         let character_rb: RigidBody;
         let is_other_dynamic: bool;
 
-        let (mut position, mut linear_velocity, max_slope_angle) =
+        let (mut position, mut linear_velocity, up, max_slope_angle) =
             if let Ok(character) = character_controllers.get_mut(rb1) {
                 is_first = true;
                 character_rb = *bodies.get(rb1).unwrap();
@@ -342,6 +661,8 @@ fn kinematic_controller_collisions(
             continue;
         }
 
+        let up = up.vector();
+
         // Iterate through contact manifolds and their contacts.
         // Each contact in a single manifold shares the same contact normal.
         for manifold in contacts.manifolds.iter() {
@@ -361,22 +682,22 @@ fn kinematic_controller_collisions(
                 deepest_penetration = deepest_penetration.max(contact.penetration);
             }
 
-            // For now, this system only handles velocity corrections for collisions against static geometry.
+            // Velocity exchange with dynamic bodies is handled by `push_dynamic_bodies`.
             if is_other_dynamic {
                 continue;
             }
 
             // Determine if the slope is climbable or if it's too steep to walk on.
-            let slope_angle = normal.angle_between(Vector::Y);
+            let slope_angle = normal.angle_between(up);
             let climbable = max_slope_angle.is_some_and(|angle| slope_angle.abs() <= angle.0);
 
             if deepest_penetration > 0.0 {
                 // If the slope is climbable, snap the velocity so that the character
                 // up and down the surface smoothly.
                 if climbable {
-                    // Points in the normal's direction in the XZ plane.
+                    // Points in the normal's direction along the ground.
                     let normal_direction_xz =
-                        normal.reject_from_normalized(Vector::Y).normalize_or_zero();
+                        normal.reject_from_normalized(up).normalize_or_zero();
 
                     // The movement speed along the direction above.
                     let linear_velocity_xz = linear_velocity.dot(normal_direction_xz);
@@ -396,7 +717,8 @@ fn kinematic_controller_collisions(
                     // *───────────────────*
 
                     let max_y_speed = -linear_velocity_xz * slope_angle.tan();
-                    linear_velocity.y = linear_velocity.y.max(max_y_speed);
+                    let y_speed = linear_velocity.dot(up);
+                    linear_velocity.0 += up * (y_speed.max(max_y_speed) - y_speed);
                 } else {
                     // The character is intersecting an unclimbable object, like a wall.
                     // We want the character to slide along the surface, similarly to
@@ -433,10 +755,11 @@ fn kinematic_controller_collisions(
                 // Apply the impulse differently depending on the slope angle.
                 if climbable {
                     // Avoid sliding down slopes.
-                    linear_velocity.y -= impulse.y.min(0.0);
+                    linear_velocity.0 -= up * impulse.dot(up).min(0.0);
                 } else {
                     // Avoid climbing up walls.
-                    impulse.y = impulse.y.max(0.0);
+                    let impulse_up = impulse.dot(up);
+                    impulse += up * (impulse_up.max(0.0) - impulse_up);
                     linear_velocity.0 -= impulse;
                 }
             }
@@ -0,0 +1,112 @@
+//! Carrying character controllers along with the platform they stand on.
+//!
+//! [`update_grounded`](super::update_grounded) records the body the ground caster hit in
+//! [`GroundedOn`]. At the start of every physics step the character is swept by however far that
+//! body moves the point the character stands on, so it rides moving and rotating platforms instead
+//! of sliding off them, without being carried into walls and ceilings. When it leaves the platform
+//! it keeps the platform's velocity.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+use super::{
+    CharacterController, UpDirection,
+    collide_and_slide::{CollideAndSlide, Sweep},
+};
+use crate::time::ClockTime;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PhysicsSchedule,
+        // Before `record_slide_start`, so collide-and-slide starts from the carried position.
+        carry_with_platform
+            .in_set(PhysicsStepSet::First)
+            .before(super::collide_and_slide::record_slide_start),
+    );
+}
+
+/// The rigid body a grounded character controller is standing on.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+#[component(storage = "SparseSet")]
+pub struct GroundedOn(pub Entity);
+
+/// The velocity the platform a character controller stands on gave it during the last step.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct PlatformVelocity(pub Vector);
+
+/// Moves character controllers along with the body they are [`GroundedOn`], sliding along
+/// anything else in the way.
+fn carry_with_platform(
+    clock: ClockTime,
+    spatial_query: SpatialQuery,
+    sensors: Query<(), With<Sensor>>,
+    mut controllers: Query<
+        (
+            Entity,
+            &Collider,
+            Option<&CollideAndSlide>,
+            &mut Position,
+            &mut Rotation,
+            &UpDirection,
+            &mut LinearVelocity,
+            &mut PlatformVelocity,
+            Option<&GroundedOn>,
+        ),
+        With<CharacterController>,
+    >,
+    platforms: Query<
+        (&Position, &LinearVelocity, Option<&AngularVelocity>),
+        Without<CharacterController>,
+    >,
+) {
+    for (
+        entity,
+        collider,
+        collide_and_slide,
+        mut position,
+        mut rotation,
+        up,
+        mut linear_velocity,
+        mut platform_velocity,
+        grounded_on,
+    ) in &mut controllers
+    {
+        let delta_time = clock.physics_delta_secs_f64(entity).adjust_precision();
+        if delta_time <= 0.0 {
+            continue;
+        }
+        let Some((platform, (platform_position, platform_linear, platform_angular))) = grounded_on
+            .and_then(|grounded_on| Some((grounded_on.0, platforms.get(grounded_on.0).ok()?)))
+        else {
+            // Keep the momentum of the platform when walking or jumping off it.
+            linear_velocity.0 += std::mem::take(&mut platform_velocity.0);
+            continue;
+        };
+
+        let angular = platform_angular.map_or(Vector::ZERO, |angular| angular.0);
+        let turn = Quaternion::from_scaled_axis(angular * delta_time);
+
+        // Rotate the offset from the platform's pivot, then follow the pivot itself.
+        let offset = position.0 - platform_position.0;
+        let moved = platform_linear.0 * delta_time + turn * offset - offset;
+
+        // The platform itself moves out of the way, everything else blocks the carry.
+        let settings = collide_and_slide.copied().unwrap_or_default();
+        let sweep = Sweep {
+            spatial_query: &spatial_query,
+            collider,
+            rotation: rotation.0,
+            filter: SpatialQueryFilter::from_excluded_entities([entity, platform]),
+            sensors: &sensors,
+            skin_width: settings.skin_width,
+        };
+        let carried = sweep.slide(position.0, moved, settings.max_iterations);
+        platform_velocity.0 = (carried - position.0) / delta_time;
+        position.0 = carried;
+
+        // Only turn around the character's up axis, tilting platforms shouldn't tip it over.
+        let up = up.vector();
+        let yaw = Quaternion::from_scaled_axis(up * angular.dot(up) * delta_time);
+        rotation.0 = (yaw * rotation.0).normalize();
+    }
+}
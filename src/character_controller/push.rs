@@ -0,0 +1,144 @@
+//! Mass-aware interaction between kinematic character controllers and dynamic rigid bodies.
+//!
+//! Kinematic bodies neither push nor get pushed by the solver in a way that feels physical, so
+//! contacts with dynamic bodies are resolved here: the character shoves what it walks into, and
+//! fast bodies that hit the character knock it back, within limits.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+use super::{CharacterController, UpDirection};
+use crate::time::ClockTime;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        PhysicsSchedule,
+        push_dynamic_bodies.in_set(NarrowPhaseSet::Last),
+    );
+}
+
+/// The most force a character controller can push dynamic bodies with.
+///
+/// Light bodies are carried along at walking speed, heavy ones only creep.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PushStrength(pub Scalar);
+
+impl Default for PushStrength {
+    fn default() -> Self {
+        Self(20.0)
+    }
+}
+
+/// How a character controller reacts to being hit by dynamic bodies.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Knockback {
+    /// The mass the character has in collisions, since kinematic bodies have none.
+    pub mass: Scalar,
+    /// Bodies approaching slower than this don't knock the character back.
+    pub min_impact_speed: Scalar,
+    /// The most speed away from the body a hit can give the character.
+    pub max_speed: Scalar,
+}
+
+impl Default for Knockback {
+    fn default() -> Self {
+        Self {
+            mass: 2.0,
+            min_impact_speed: 2.0,
+            max_speed: 6.0,
+        }
+    }
+}
+
+/// Exchanges momentum between character controllers and the dynamic bodies they touch.
+fn push_dynamic_bodies(
+    clock: ClockTime,
+    collisions: Collisions,
+    collider_rbs: Query<&ColliderOf, Without<Sensor>>,
+    mut controllers: Query<
+        (
+            &mut LinearVelocity,
+            &UpDirection,
+            Option<&PushStrength>,
+            Option<&Knockback>,
+        ),
+        With<CharacterController>,
+    >,
+    mut bodies: Query<(&RigidBody, &ComputedMass, &mut LinearVelocity), Without<CharacterController>>,
+) {
+    for contacts in collisions.iter() {
+        let Ok([&ColliderOf { body: rb1 }, &ColliderOf { body: rb2 }]) =
+            collider_rbs.get_many([contacts.collider1, contacts.collider2])
+        else {
+            continue;
+        };
+
+        let (is_first, character, other) = if controllers.contains(rb1) {
+            (true, rb1, rb2)
+        } else if controllers.contains(rb2) {
+            (false, rb2, rb1)
+        } else {
+            continue;
+        };
+
+        let Ok((mut character_velocity, up, push_strength, knockback)) =
+            controllers.get_mut(character)
+        else {
+            continue;
+        };
+        let Ok((rigid_body, mass, mut body_velocity)) = bodies.get_mut(other) else {
+            continue;
+        };
+        if !rigid_body.is_dynamic() || mass.value() <= 0.0 {
+            continue;
+        }
+
+        // One response per pair is enough, and all manifolds of a pair face roughly the same way.
+        let Some(manifold) = contacts.manifolds.first() else {
+            continue;
+        };
+        // Points from the other body towards the character.
+        let normal = if is_first {
+            -manifold.normal
+        } else {
+            manifold.normal
+        };
+
+        // How fast the body itself comes at the character, before it is pushed. Running into a
+        // resting body doesn't knock the character back.
+        let approach_speed = body_velocity.dot(normal);
+
+        if let Some(push_strength) = push_strength {
+            // Only push along the ground, so standing on a crate doesn't drive it into the ground.
+            let push_direction = (-normal)
+                .reject_from_normalized(up.vector())
+                .normalize_or_zero();
+            let closing_speed = (character_velocity.0 - body_velocity.0).dot(push_direction);
+            if push_direction != Vector::ZERO && closing_speed > 0.0 {
+                let delta_time = clock.physics_delta_secs_f64(character).adjust_precision();
+                let impulse = (closing_speed * mass.value()).min(push_strength.0 * delta_time);
+                body_velocity.0 += push_direction * impulse / mass.value();
+
+                // Walk no faster than the body gives way, so the solver doesn't shove it along
+                // with the infinite mass of a kinematic body.
+                let excess = (character_velocity.0 - body_velocity.0).dot(push_direction);
+                if excess > 0.0 {
+                    character_velocity.0 -= push_direction * excess;
+                }
+            }
+        }
+
+        if let Some(knockback) = knockback
+            && approach_speed > knockback.min_impact_speed
+        {
+            // Raise the speed away from the body to the knockback, without stacking up over the
+            // steps the bodies stay in contact.
+            let share = mass.value() / (mass.value() + knockback.mass);
+            let knockback_speed = (approach_speed * share).min(knockback.max_speed);
+            let away_speed = character_velocity.dot(normal);
+            if away_speed < knockback_speed {
+                character_velocity.0 += normal * (knockback_speed - away_speed);
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Finding a free spot to put a character controller at, without dropping it inside geometry.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+/// How far below the origin [`find_safe_position`] looks for the floor.
+const MAX_DROP: Scalar = 50.0;
+/// How far a character is pushed up per step while it is inside geometry.
+const DEPENETRATION_STEP: Scalar = 0.1;
+const MAX_DEPENETRATION_STEPS: usize = 50;
+/// Gap kept between the collider and the floor it is dropped onto.
+const SKIN_WIDTH: Scalar = 0.02;
+
+/// A position for a character with `collider` near `origin`: first pushed up along `up` out of
+/// any geometry it is inside of, then dropped down onto the floor below. Sensors are ignored.
+///
+/// Returns `None` if it is still inside geometry after being pushed up a few units. Stays in the
+/// air if there is no floor in reach.
+pub fn find_safe_position(
+    spatial_query: &SpatialQuery,
+    sensors: &Query<(), With<Sensor>>,
+    collider: &Collider,
+    rotation: Quaternion,
+    origin: Vector,
+    up: Dir3,
+    filter: &SpatialQueryFilter,
+) -> Option<Vector> {
+    let up_vector = up.as_vec3().adjust_precision();
+    let blocked = |position: Vector| {
+        is_penetrating(spatial_query, sensors, collider, rotation, position, filter)
+    };
+
+    let mut position = origin;
+    let mut steps = 0;
+    while blocked(position) {
+        if steps == MAX_DEPENETRATION_STEPS {
+            return None;
+        }
+        position += up_vector * DEPENETRATION_STEP;
+        steps += 1;
+    }
+
+    if let Some(hit) = spatial_query.cast_shape_predicate(
+        collider,
+        position,
+        rotation,
+        -up,
+        &ShapeCastConfig {
+            max_distance: MAX_DROP,
+            ..default()
+        },
+        filter,
+        &|entity| !sensors.contains(entity),
+    ) {
+        position -= up_vector * (hit.distance - SKIN_WIDTH).max(0.0);
+    }
+    Some(position)
+}
+
+/// Whether a character with `collider` at `position` is inside any geometry. Sensors are ignored.
+pub fn is_penetrating(
+    spatial_query: &SpatialQuery,
+    sensors: &Query<(), With<Sensor>>,
+    collider: &Collider,
+    rotation: Quaternion,
+    position: Vector,
+    filter: &SpatialQueryFilter,
+) -> bool {
+    spatial_query
+        .shape_intersections(collider, position, rotation, filter)
+        .into_iter()
+        .any(|entity| !sensors.contains(entity))
+}
@@ -0,0 +1,248 @@
+//! Standing, crouching and lying prone, and sprinting.
+//!
+//! Every [`Stance`] has its own collider. Changing stance swaps the collider and the shape of the
+//! ground caster while keeping the feet in place, and getting up only happens once there is room
+//! for the taller collider. The stance and [`Sprint`] scale the movement acceleration through
+//! [`MovementSpeedScale`].
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+use super::{CharacterController, MovementInput, UpDirection};
+use crate::time::ClockTime;
+
+/// How upright a character controller is.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Stance {
+    #[default]
+    Stand,
+    Crouch,
+    Prone,
+}
+
+/// The collider and speed of one [`Stance`].
+#[derive(Clone, Debug)]
+pub struct StanceShape {
+    pub collider: Collider,
+    /// Scales the movement acceleration while in this stance.
+    pub speed_scale: Scalar,
+}
+
+/// The shapes of a character controller in every [`Stance`].
+#[derive(Component, Clone, Debug)]
+#[require(Stance, MovementSpeedScale)]
+pub struct Stances {
+    pub stand: StanceShape,
+    pub crouch: StanceShape,
+    pub prone: StanceShape,
+}
+
+impl Stances {
+    /// Capsules for a character that stands as `Collider::capsule(radius, length)`.
+    ///
+    /// Crouching is two thirds of the standing height, lying prone shrinks the capsule to a ball.
+    pub fn capsule(radius: Scalar, length: Scalar) -> Self {
+        let crouch_length = ((length + 2.0 * radius) * 2.0 / 3.0 - 2.0 * radius).max(0.0);
+        Self {
+            stand: StanceShape {
+                collider: Collider::capsule(radius, length),
+                speed_scale: 1.0,
+            },
+            crouch: StanceShape {
+                collider: Collider::capsule(radius, crouch_length),
+                speed_scale: 0.5,
+            },
+            prone: StanceShape {
+                collider: Collider::sphere(radius),
+                speed_scale: 0.25,
+            },
+        }
+    }
+
+    pub fn get(&self, stance: Stance) -> &StanceShape {
+        match stance {
+            Stance::Stand => &self.stand,
+            Stance::Crouch => &self.crouch,
+            Stance::Prone => &self.prone,
+        }
+    }
+
+    /// Puts a character controller in `target`, swapping in its collider and ground caster shape.
+    ///
+    /// Neither moves the character nor checks that there is room for it.
+    pub fn set_stance(
+        &self,
+        target: Stance,
+        stance: &mut Stance,
+        collider: &mut Collider,
+        caster: &mut ShapeCaster,
+    ) {
+        *collider = self.get(target).collider.clone();
+        let mut caster_shape = collider.clone();
+        caster_shape.set_scale(Vector::ONE * 0.99, 10);
+        caster.shape = caster_shape;
+        *stance = target;
+    }
+}
+
+/// Sprinting at the cost of stamina, which recovers while not sprinting.
+///
+/// Stamina is in seconds of sprinting.
+#[derive(Component, Clone, Debug)]
+pub struct Sprint {
+    pub speed_scale: Scalar,
+    pub stamina: Scalar,
+    pub max_stamina: Scalar,
+    /// Stamina recovered per second.
+    pub recovery_rate: Scalar,
+    /// Stamina needed to start sprinting, so an exhausted character can't sprint in bursts.
+    pub min_stamina: Scalar,
+    sprinting: bool,
+}
+
+impl Default for Sprint {
+    fn default() -> Self {
+        Self {
+            speed_scale: 1.6,
+            stamina: 3.0,
+            max_stamina: 3.0,
+            recovery_rate: 0.75,
+            min_stamina: 0.5,
+            sprinting: false,
+        }
+    }
+}
+
+impl Sprint {
+    pub fn is_sprinting(&self) -> bool {
+        self.sprinting
+    }
+
+    /// Stops sprinting, with full stamina.
+    pub fn rest(&mut self) {
+        self.sprinting = false;
+        self.stamina = self.max_stamina;
+    }
+}
+
+/// Scales the movement acceleration of a character controller, such as for stances and sprinting.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct MovementSpeedScale(pub Scalar);
+
+impl Default for MovementSpeedScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// The height of a collider along its local up axis.
+pub(super) fn height(collider: &Collider) -> Scalar {
+    let aabb = collider.aabb(Vector::ZERO, Rotation::default());
+    aabb.max.y - aabb.min.y
+}
+
+/// Changes the [`Stance`] of character controllers according to their [`MovementInput`], and
+/// updates their [`MovementSpeedScale`].
+pub(super) fn update_stance(
+    clock: ClockTime,
+    spatial_query: SpatialQuery,
+    sensors: Query<(), With<Sensor>>,
+    mut controllers: Query<
+        (
+            Entity,
+            &Stances,
+            &mut Stance,
+            &mut Collider,
+            &mut ShapeCaster,
+            &mut Position,
+            &Rotation,
+            &UpDirection,
+            &MovementInput,
+            Option<&mut Sprint>,
+            &mut MovementSpeedScale,
+        ),
+        With<CharacterController>,
+    >,
+) {
+    for (
+        entity,
+        stances,
+        mut stance,
+        mut collider,
+        mut caster,
+        mut position,
+        rotation,
+        up,
+        input,
+        sprint,
+        mut speed_scale,
+    ) in &mut controllers
+    {
+        let delta_time = clock.delta_secs_f64(entity).adjust_precision();
+        let desired = if input.prone {
+            Stance::Prone
+        } else if input.crouch_held {
+            Stance::Crouch
+        } else {
+            Stance::Stand
+        };
+
+        if desired != *stance {
+            let current_height = height(&collider);
+            let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+            // Where the center of the collider of `target` is with the feet kept in place.
+            let center = |target: Stance| {
+                let offset = (height(&stances.get(target).collider) - current_height) / 2.0;
+                position.0 + up.vector() * offset
+            };
+            let fits = |target: Stance| {
+                // Slightly smaller, so the ground under the feet doesn't count as blocking.
+                let mut shape = stances.get(target).collider.clone();
+                shape.set_scale(Vector::ONE * 0.95, 10);
+                spatial_query
+                    .shape_intersections(&shape, center(target), rotation.0, &filter)
+                    .into_iter()
+                    .all(|hit| sensors.contains(hit))
+            };
+
+            let reachable = |target: Stance| {
+                height(&stances.get(target).collider) <= current_height || fits(target)
+            };
+
+            // Getting up needs headroom. If there isn't enough to stand up from prone, at least
+            // try crouching.
+            let fallback = (desired == Stance::Stand && *stance == Stance::Prone)
+                .then_some(Stance::Crouch);
+            if let Some(target) = [Some(desired), fallback]
+                .into_iter()
+                .flatten()
+                .find(|&target| reachable(target))
+                && target != *stance
+            {
+                position.0 = center(target);
+                stances.set_stance(target, &mut stance, &mut collider, &mut caster);
+            }
+        }
+
+        let mut scale = stances.get(*stance).speed_scale;
+        if let Some(mut sprint) = sprint {
+            let wants_to_sprint =
+                input.sprint_held && input.direction != Vector2::ZERO && *stance == Stance::Stand;
+            sprint.sprinting = wants_to_sprint
+                && if sprint.sprinting {
+                    sprint.stamina > 0.0
+                } else {
+                    sprint.stamina >= sprint.min_stamina
+                };
+
+            if sprint.sprinting {
+                sprint.stamina = (sprint.stamina - delta_time).max(0.0);
+                scale *= sprint.speed_scale;
+            } else {
+                sprint.stamina =
+                    (sprint.stamina + sprint.recovery_rate * delta_time).min(sprint.max_stamina);
+            }
+        }
+        speed_scale.0 = scale;
+    }
+}
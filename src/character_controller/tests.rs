@@ -0,0 +1,678 @@
+//! Headless tests that step the character controller and physics on a manual clock.
+
+use std::time::Duration;
+
+use avian3d::{math::*, prelude::*};
+use bevy::{prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use super::*;
+use crate::input::ActionState;
+use crate::simple_scene::game::{CameraState, MainCamera};
+use crate::time::{ClockDomain, ClockPlugin, Clocks, OnClock, VirtualClock};
+
+/// An app with physics and the character controller, advancing `1 / frame_rate` seconds on
+/// every update.
+fn app(frame_rate: f64) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        TransformPlugin,
+        AssetPlugin::default(),
+        StatesPlugin,
+        PhysicsPlugins::default(),
+        CharacterControllerPlugin,
+    ))
+    .init_asset::<Mesh>()
+    .init_state::<CameraState>()
+    .init_resource::<ActionState>()
+    .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / frame_rate,
+    )))
+    .add_systems(FixedUpdate, drive.before(update_grounded));
+    app.world_mut().spawn((MainCamera, Transform::default()));
+    app
+}
+
+/// Runs the app for `seconds` of game time.
+fn run(app: &mut App, frame_rate: f64, seconds: f64) {
+    for _ in 0..(seconds * frame_rate).round() as usize {
+        app.update();
+    }
+}
+
+/// Keeps the velocity of a character along the ground at a fixed value, like held input.
+#[derive(Component, Clone, Copy)]
+struct Drive(Vector);
+
+fn drive(mut query: Query<(&Drive, &UpDirection, &mut LinearVelocity)>) {
+    for (drive, up, mut linear_velocity) in &mut query {
+        let up = up.vector();
+        linear_velocity.0 = up * linear_velocity.dot(up) + drive.0.reject_from_normalized(up);
+    }
+}
+
+/// The height of the center of a standing [`character`] above the ground.
+const STANDING_HEIGHT: Scalar = 0.9;
+const RADIUS: Scalar = 0.4;
+
+/// A capsule character at `position`, without damping so driven and initial velocities stick.
+fn character(position: Vector) -> impl Bundle {
+    (
+        CharacterControllerBundle::new(Collider::capsule(RADIUS, 1.0), Vector::NEG_Y * 9.81)
+            .with_movement_bundle(
+                MovementBundle::new(30.0, 1.0, 7.0, PI * 0.45).with_air_control(9.0, 1.0),
+            ),
+        Position(position),
+        Transform::from_translation(position.f32()),
+    )
+}
+
+/// A static box with its top at `top`, centered on `center` along the ground.
+fn block(center: Vector, size: Vector, top: Scalar) -> impl Bundle {
+    let position = center.with_y(top - size.y / 2.0);
+    (
+        RigidBody::Static,
+        Collider::cuboid(size.x, size.y, size.z),
+        Position(position),
+        Transform::from_translation(position.f32()),
+    )
+}
+
+fn ground(app: &mut App) {
+    app.world_mut()
+        .spawn(block(Vector::ZERO, Vector::new(40.0, 1.0, 40.0), 0.0));
+}
+
+fn position(app: &App, entity: Entity) -> Vector {
+    app.world().get::<Position>(entity).unwrap().0
+}
+
+/// Drives a character with and without [`CollideAndSlide`] into the same geometry, each in its
+/// own world, and returns where they ended up.
+fn compare(geometry: impl Fn(&mut App), seconds: f64) -> (Vector, Vector) {
+    let run_character = |collide_and_slide: bool| {
+        let mut app = app(60.0);
+        ground(&mut app);
+        geometry(&mut app);
+        let mut character = app.world_mut().spawn((
+            character(Vector::new(0.0, STANDING_HEIGHT + 0.02, 0.0)),
+            Drive(Vector::X * 2.0),
+        ));
+        if collide_and_slide {
+            character.insert(CollideAndSlide::default());
+        }
+        let character = character.id();
+        run(&mut app, 60.0, seconds);
+        position(&app, character)
+    };
+    (run_character(true), run_character(false))
+}
+
+#[test]
+fn collide_and_slide_and_contact_response_stop_at_walls() {
+    let wall_face = 3.0;
+    let (sliding, contacts) = compare(
+        |app| {
+            app.world_mut().spawn(block(
+                Vector::new(wall_face + 0.5, 0.0, 0.0),
+                Vector::new(1.0, 4.0, 10.0),
+                4.0,
+            ));
+        },
+        2.0,
+    );
+
+    // Both reach the wall without ending up in it or climbing it.
+    for position in [sliding, contacts] {
+        assert!(position.x > wall_face - RADIUS - 0.1, "{position}");
+        assert!(position.x < wall_face - RADIUS + 0.05, "{position}");
+        assert!((position.y - STANDING_HEIGHT).abs() < 0.1, "{position}");
+    }
+    // Collide-and-slide keeps its skin width to the wall.
+    assert!(sliding.x <= wall_face - RADIUS);
+}
+
+#[test]
+fn collide_and_slide_walks_up_steps() {
+    let step_start = 3.0;
+    let step_height = 0.2;
+    let (sliding, contacts) = compare(
+        |app| {
+            app.world_mut().spawn(block(
+                Vector::new(step_start + 2.0, 0.0, 0.0),
+                Vector::new(4.0, step_height, 10.0),
+                step_height,
+            ));
+        },
+        2.0,
+    );
+
+    // Collide-and-slide steps onto the ledge and keeps walking on top of it.
+    assert!(sliding.x > step_start + 0.5, "{sliding}");
+    assert!(
+        (sliding.y - (step_height + STANDING_HEIGHT)).abs() < 0.05,
+        "{sliding}"
+    );
+
+    // The contact response either climbs it as well or stops in front of it, but it never ends up
+    // inside the step.
+    if contacts.x > step_start - RADIUS + 0.05 {
+        assert!(
+            contacts.y > step_height + STANDING_HEIGHT - 0.05,
+            "{contacts}"
+        );
+    }
+}
+
+/// Where a character thrown sideways from above the ground ends up after `seconds` at
+/// `frame_rate`, having fallen, landed and slid along a wall.
+fn throw_character(frame_rate: f64, seconds: f64) -> Vector {
+    let mut app = app(frame_rate);
+    ground(&mut app);
+    app.world_mut().spawn(block(
+        Vector::new(2.0, 0.0, 0.0),
+        Vector::new(1.0, 4.0, 10.0),
+        4.0,
+    ));
+    let character = app
+        .world_mut()
+        .spawn((
+            character(Vector::new(0.0, 3.0, 0.0)),
+            CollideAndSlide::default(),
+            LinearVelocity(Vector::new(3.0, 0.0, 1.0)),
+        ))
+        .id();
+    run(&mut app, frame_rate, seconds);
+    position(&app, character)
+}
+
+#[test]
+fn movement_is_the_same_at_any_frame_rate() {
+    // Not a whole number of fixed steps, so no frame rate lands right on a step boundary.
+    let seconds = 0.9;
+    let reference = throw_character(60.0, seconds);
+    // Landed against the wall, and still sliding along it.
+    assert!((reference.y - STANDING_HEIGHT).abs() < 0.1, "{reference}");
+    assert!(reference.x > 1.5 - RADIUS - 0.1, "{reference}");
+    assert!(reference.z > 0.5, "{reference}");
+
+    for frame_rate in [30.0, 240.0] {
+        let position = throw_character(frame_rate, seconds);
+        assert!(
+            position.distance(reference) < 1e-3,
+            "{position} at {frame_rate} Hz, {reference} at 60 Hz"
+        );
+    }
+}
+
+#[test]
+fn collide_and_slide_runs_at_the_physics_clock_speed() {
+    let mut app = app(60.0);
+    app.add_plugins(ClockPlugin);
+    let physics = app.world().resource::<Clocks>().get(ClockDomain::Physics);
+    app.world_mut()
+        .get_mut::<VirtualClock>(physics)
+        .unwrap()
+        .speed = 0.5;
+
+    // Floating in place, so nothing but its own velocity moves it.
+    let speed = 2.0;
+    let character = app
+        .world_mut()
+        .spawn((
+            character(Vector::ZERO),
+            CollideAndSlide::default(),
+            OnClock(physics),
+            LinearVelocity(Vector::X * speed),
+        ))
+        .insert(DefaultGravity(Vector::ZERO))
+        .id();
+    run(&mut app, 60.0, 0.9);
+
+    // Physics ran at half speed, and the character moved through all of the physics time instead
+    // of having the physics clock applied to it twice.
+    let fixed_time = app.world().resource::<Time<Fixed>>().elapsed_secs_f64();
+    let physics_time = app.world().resource::<Time<Physics>>().elapsed_secs_f64();
+    assert!(fixed_time > 0.8, "{fixed_time}");
+    assert!(
+        (physics_time - fixed_time * 0.5).abs() < 1e-6,
+        "{physics_time}"
+    );
+    let moved = position(&app, character).x;
+    let expected = speed * physics_time.adjust_precision();
+    assert!(
+        (moved - expected).abs() < 1e-3,
+        "moved {moved}, expected {expected}"
+    );
+}
+
+/// A dynamic unit crate resting on the ground at `x`.
+fn crate_at(x: Scalar, mass: Scalar) -> impl Bundle {
+    let position = Vector::new(x, 0.5, 0.0);
+    (
+        RigidBody::Dynamic,
+        Collider::cuboid(1.0, 1.0, 1.0),
+        Mass(mass),
+        Position(position),
+        Transform::from_translation(position.f32()),
+    )
+}
+
+/// Walks a character into a crate of `mass` for a while and returns how far the crate moved and
+/// how fast it ended up.
+fn push_crate(mass: Scalar) -> (Scalar, Scalar) {
+    let mut app = app(60.0);
+    ground(&mut app);
+    let start = RADIUS + 0.6;
+    let crate_entity = app.world_mut().spawn(crate_at(start, mass)).id();
+    app.world_mut().spawn((
+        character(Vector::new(0.0, STANDING_HEIGHT + 0.02, 0.0)),
+        CollideAndSlide::default(),
+        PushStrength::default(),
+        Drive(Vector::X * 2.0),
+    ));
+    run(&mut app, 60.0, 1.5);
+    let speed = app.world().get::<LinearVelocity>(crate_entity).unwrap().x;
+    (position(&app, crate_entity).x - start, speed)
+}
+
+#[test]
+fn pushed_crates_move_by_their_mass() {
+    // A light crate is carried along at about walking speed, never faster.
+    let (light_moved, light_speed) = push_crate(2.0);
+    assert!(light_moved > 1.0, "{light_moved}");
+    assert!(light_speed < 2.0 + 0.1, "{light_speed}");
+
+    // Friction holds a heavy crate in place, as the push strength is too low to move it.
+    let (heavy_moved, _) = push_crate(200.0);
+    assert!(heavy_moved.abs() < 0.05, "{heavy_moved}");
+}
+
+#[test]
+fn fast_crates_knock_back_by_at_most_max_speed() {
+    let mut app = app(60.0);
+    ground(&mut app);
+    let knockback = Knockback::default();
+    let character = app
+        .world_mut()
+        .spawn((
+            character(Vector::new(0.0, STANDING_HEIGHT + 0.02, 0.0)),
+            CollideAndSlide::default(),
+            PushStrength::default(),
+            knockback,
+        ))
+        .id();
+    // Thrown at the character, floating at its height.
+    app.world_mut().spawn((
+        crate_at(4.0, 10.0),
+        GravityScale(0.0),
+        LinearVelocity(Vector::NEG_X * 20.0),
+    ));
+
+    let mut fastest: Scalar = 0.0;
+    for _ in 0..60 {
+        app.update();
+        let velocity = app.world().get::<LinearVelocity>(character).unwrap();
+        fastest = fastest.max(-velocity.x);
+    }
+
+    // Knocked away from the crate, but no faster than the knockback allows.
+    assert!(fastest > knockback.min_impact_speed, "{fastest}");
+    assert!(fastest <= knockback.max_speed + 1e-3, "{fastest}");
+    assert!(position(&app, character).x < 0.0);
+}
+
+fn traversal_mode(app: &App, entity: Entity) -> Option<TraversalMode> {
+    app.world().get::<TraversalMode>(entity).copied()
+}
+
+fn input(app: &mut App, entity: Entity) -> Mut<'_, MovementInput> {
+    app.world_mut().get_mut::<MovementInput>(entity).unwrap()
+}
+
+/// A standing [`Climber`] holding forward, away from the default camera.
+fn climber(app: &mut App, position: Vector, forward: Scalar) -> Entity {
+    app.world_mut()
+        .spawn((
+            character(position),
+            Climber::default(),
+            MovementInput {
+                direction: Vector2::new(0.0, forward),
+                ..default()
+            },
+        ))
+        .id()
+}
+
+#[test]
+fn climbers_climb_ladders_and_drop_off_them() {
+    let mut app = app(60.0);
+    ground(&mut app);
+    // Just in front of the character, so it touches it.
+    app.world_mut().spawn((
+        Ladder::default(),
+        block(
+            Vector::new(0.0, 0.0, -RADIUS - 0.05),
+            Vector::new(1.0, 4.0, 0.2),
+            4.0,
+        ),
+    ));
+    let start = STANDING_HEIGHT + 0.02;
+    let character = climber(&mut app, Vector::new(0.0, start, 0.0), 1.0);
+
+    run(&mut app, 60.0, 0.5);
+    assert!(matches!(
+        traversal_mode(&app, character),
+        Some(TraversalMode::Climbing { .. })
+    ));
+    let climbed = position(&app, character).y;
+    assert!(climbed > start + 0.5, "{climbed}");
+
+    // Crouching lets go, and the character falls back down.
+    let mut held = input(&mut app, character);
+    held.crouch_held = true;
+    held.direction = Vector2::ZERO;
+    run(&mut app, 60.0, 1.0);
+    assert_eq!(traversal_mode(&app, character), None);
+    assert!(position(&app, character).y < climbed - 0.5);
+}
+
+/// A [`Ledge`] wall with its face at z = -1 and its top at [`LEDGE_TOP`].
+fn ledge_wall(app: &mut App) {
+    app.world_mut().spawn((
+        Ledge,
+        block(
+            Vector::new(0.0, 0.0, -1.5),
+            Vector::new(4.0, LEDGE_TOP, 1.0),
+            LEDGE_TOP,
+        ),
+    ));
+}
+
+const LEDGE_TOP: Scalar = 2.2;
+
+/// A climber falling past the [`ledge_wall`], reaching towards it without pulling itself up.
+fn falling_climber(app: &mut App) -> Entity {
+    climber(app, Vector::new(0.0, 1.7, -0.45), 0.3)
+}
+
+#[test]
+fn climbers_grab_ledges_and_mantle_onto_them() {
+    let mut app = app(60.0);
+    ground(&mut app);
+    ledge_wall(&mut app);
+    let character = falling_climber(&mut app);
+
+    // Hanging off the wall with the hands on the edge, held up against gravity.
+    run(&mut app, 60.0, 0.5);
+    assert!(matches!(
+        traversal_mode(&app, character),
+        Some(TraversalMode::Hanging { .. })
+    ));
+    let hanging = position(&app, character);
+    let expected = Vector::new(0.0, LEDGE_TOP - STANDING_HEIGHT * 0.8, -1.0 + RADIUS + 0.02);
+    assert!(hanging.distance(expected) < 0.05, "{hanging}");
+
+    // Pulling up climbs over the edge and stands on top.
+    input(&mut app, character).direction.y = 1.0;
+    run(&mut app, 60.0, 1.0);
+    assert_eq!(traversal_mode(&app, character), None);
+    let on_top = position(&app, character);
+    assert!(on_top.z < -1.0 - RADIUS, "{on_top}");
+    assert!(
+        (on_top.y - (LEDGE_TOP + STANDING_HEIGHT)).abs() < 0.1,
+        "{on_top}"
+    );
+}
+
+#[test]
+fn climbers_only_mantle_where_they_can_stand() {
+    let mut app = app(60.0);
+    ground(&mut app);
+    ledge_wall(&mut app);
+    // A ceiling above the top of the ledge, too low to stand under.
+    app.world_mut().spawn(block(
+        Vector::new(0.0, 0.0, -2.0),
+        Vector::new(4.0, 0.5, 2.0),
+        LEDGE_TOP + 1.5,
+    ));
+    let character = falling_climber(&mut app);
+    run(&mut app, 60.0, 0.5);
+    let hanging = position(&app, character);
+
+    input(&mut app, character).direction.y = 1.0;
+    run(&mut app, 60.0, 1.0);
+    assert!(matches!(
+        traversal_mode(&app, character),
+        Some(TraversalMode::Hanging { .. })
+    ));
+    assert!(position(&app, character).distance(hanging) < 1e-3);
+}
+
+/// A character with [`Stances`] and [`Sprint`] standing on the ground.
+fn stancing_character(app: &mut App) -> Entity {
+    let character = app
+        .world_mut()
+        .spawn((
+            character(Vector::new(0.0, STANDING_HEIGHT + 0.02, 0.0)),
+            Stances::capsule(RADIUS, 1.0),
+            Sprint::default(),
+        ))
+        .id();
+    run(app, 60.0, 0.3);
+    character
+}
+
+fn stance(app: &App, entity: Entity) -> Stance {
+    *app.world().get::<Stance>(entity).unwrap()
+}
+
+/// The height of the feet of a character in `stance`.
+fn feet(app: &App, entity: Entity) -> Scalar {
+    let stances = app.world().get::<Stances>(entity).unwrap();
+    let aabb = stances
+        .get(stance(app, entity))
+        .collider
+        .aabb(Vector::ZERO, Rotation::default());
+    position(app, entity).y + aabb.min.y
+}
+
+/// A ceiling over a character on the ground at the origin, low enough to crouch under but not to
+/// stand.
+fn low_ceiling(app: &mut App) -> Entity {
+    app.world_mut()
+        .spawn(block(Vector::ZERO, Vector::new(4.0, 0.5, 4.0), 1.9))
+        .id()
+}
+
+#[test]
+fn characters_only_stand_up_where_there_is_room() {
+    let mut app = app(60.0);
+    ground(&mut app);
+    let character = stancing_character(&mut app);
+    let standing_feet = feet(&app, character);
+
+    input(&mut app, character).crouch_held = true;
+    run(&mut app, 60.0, 0.2);
+    assert_eq!(stance(&app, character), Stance::Crouch);
+    assert!((feet(&app, character) - standing_feet).abs() < 0.02);
+
+    // Letting go of crouch under the ceiling keeps the character crouched, with its feet in place.
+    let ceiling = low_ceiling(&mut app);
+    input(&mut app, character).crouch_held = false;
+    run(&mut app, 60.0, 0.5);
+    assert_eq!(stance(&app, character), Stance::Crouch);
+    assert!((feet(&app, character) - standing_feet).abs() < 0.02);
+
+    // It stands up once the ceiling is gone.
+    app.world_mut().despawn(ceiling);
+    run(&mut app, 60.0, 0.2);
+    assert_eq!(stance(&app, character), Stance::Stand);
+    assert!((feet(&app, character) - standing_feet).abs() < 0.02);
+}
+
+#[test]
+fn characters_getting_up_from_prone_without_headroom_crouch() {
+    let mut app = app(60.0);
+    ground(&mut app);
+    let character = stancing_character(&mut app);
+    let standing_feet = feet(&app, character);
+
+    input(&mut app, character).prone = true;
+    run(&mut app, 60.0, 0.2);
+    assert_eq!(stance(&app, character), Stance::Prone);
+
+    low_ceiling(&mut app);
+    input(&mut app, character).prone = false;
+    run(&mut app, 60.0, 0.2);
+    assert_eq!(stance(&app, character), Stance::Crouch);
+    assert!((feet(&app, character) - standing_feet).abs() < 0.02);
+}
+
+#[test]
+fn sprinting_needs_stamina_to_start() {
+    let mut app = app(60.0);
+    ground(&mut app);
+    let character = stancing_character(&mut app);
+    let sprinting = |app: &App| app.world().get::<Sprint>(character).unwrap().is_sprinting();
+    let speed_scale = |app: &App| app.world().get::<MovementSpeedScale>(character).unwrap().0;
+
+    let mut held = input(&mut app, character);
+    held.sprint_held = true;
+    held.direction = Vector2::Y;
+    run(&mut app, 60.0, 0.2);
+    assert!(sprinting(&app));
+    assert_eq!(speed_scale(&app), Sprint::default().speed_scale);
+
+    // Sprinting stops once the stamina runs out, and doesn't start again until some of it is back.
+    let sprint = Sprint::default();
+    run(&mut app, 60.0, sprint.max_stamina as f64);
+    assert!(!sprinting(&app));
+    assert_eq!(speed_scale(&app), 1.0);
+    run(&mut app, 60.0, 0.3);
+    assert!(!sprinting(&app));
+
+    // Recovering passes the stamina needed to start after about two thirds of a second.
+    run(&mut app, 60.0, 0.3);
+    assert!(sprinting(&app));
+}
+
+/// A pool of `fluid` over the ground with its surface at `surface`.
+fn pool(app: &mut App, fluid: FluidVolume, surface: Scalar) {
+    app.world_mut().spawn((
+        fluid,
+        block(Vector::ZERO, Vector::new(20.0, surface, 20.0), surface),
+    ));
+}
+
+fn submerged(app: &App, entity: Entity) -> Option<Submerged> {
+    app.world().get::<Submerged>(entity).copied()
+}
+
+#[test]
+fn characters_float_in_water() {
+    // Water counters a little more than gravity, so a character floats with its top just out of
+    // the water, where buoyancy and gravity even out.
+    let mut app = app(60.0);
+    ground(&mut app);
+    pool(&mut app, FluidVolume::water(), 4.0);
+    let character = app.world_mut().spawn(character(Vector::Y * 2.0)).id();
+    run(&mut app, 60.0, 6.0);
+    let floating = submerged(&app, character).unwrap();
+    let water = FluidVolume::water();
+    let balanced = 1.0 / (1.0 - water.gravity_scale + water.buoyancy);
+    assert!(
+        (floating.fraction - balanced).abs() < 0.05,
+        "{}",
+        floating.fraction
+    );
+    assert!(app.world().get::<Swimming>(character).is_some());
+}
+
+#[test]
+fn characters_sink_slowly_in_mud() {
+    // Mud barely counters gravity, but slows a fall down to a crawl.
+    let mut app = app(60.0);
+    ground(&mut app);
+    pool(&mut app, FluidVolume::mud(), 6.0);
+    let character = app.world_mut().spawn(character(Vector::Y * 4.0)).id();
+    run(&mut app, 60.0, 0.5);
+    assert!(submerged(&app, character).is_some_and(|submerged| submerged.fraction == 1.0));
+    let falling = -app.world().get::<LinearVelocity>(character).unwrap().y;
+    assert!(falling > 0.0 && falling < 1.0, "{falling}");
+    assert!(app.world().get::<Swimming>(character).is_none());
+}
+
+#[test]
+fn fluid_drag_slows_characters_down_by_depth() {
+    let mut app = app(60.0);
+    ground(&mut app);
+    pool(&mut app, FluidVolume::water(), 6.0);
+    let speed = 4.0;
+    let character = app
+        .world_mut()
+        .spawn((
+            character(Vector::Y * 3.0),
+            LinearVelocity(Vector::X * speed),
+        ))
+        .insert(DefaultGravity(Vector::ZERO))
+        .id();
+    run(&mut app, 60.0, 1.0);
+
+    // Fully submerged from the first step on, and nothing else slows it down.
+    let elapsed = app.world().resource::<Time<Fixed>>().elapsed_secs_f64() as Scalar;
+    let expected = speed * (-FluidVolume::water().drag * elapsed).exp();
+    let velocity = app.world().get::<LinearVelocity>(character).unwrap().x;
+    assert!(
+        (velocity - expected).abs() < 0.05,
+        "{velocity} != {expected}"
+    );
+}
+
+/// A [`GravityField`] sensor filling the space around the origin.
+fn gravity_field(app: &mut App, field: GravityField) {
+    app.world_mut()
+        .spawn((field, block(Vector::ZERO, Vector::splat(100.0), 50.0)));
+}
+
+fn gravity(app: &App, entity: Entity) -> Vector {
+    app.world().get::<ControllerGravity>(entity).unwrap().0
+}
+
+#[test]
+fn the_gravity_field_with_the_highest_priority_wins() {
+    let mut app = app(60.0);
+    gravity_field(
+        &mut app,
+        GravityField::directional(Dir3::Z, 7.0).with_priority(1),
+    );
+    gravity_field(&mut app, GravityField::directional(Dir3::NEG_X, 5.0));
+    let inside = app.world_mut().spawn(character(Vector::ZERO)).id();
+    let outside = app
+        .world_mut()
+        .spawn(character(Vector::new(80.0, 0.0, 0.0)))
+        .id();
+    run(&mut app, 60.0, 0.1);
+
+    assert!(gravity(&app, inside).distance(Vector::Z * 7.0) < 1e-4);
+    assert!(gravity(&app, outside).distance(Vector::NEG_Y * 9.81) < 1e-4);
+}
+
+#[test]
+fn up_direction_turns_away_from_the_field_gravity_gradually() {
+    let mut app = app(60.0);
+    gravity_field(&mut app, GravityField::directional(Dir3::Z, 9.81));
+    let character = app.world_mut().spawn(character(Vector::ZERO)).id();
+    let up = |app: &App| app.world().get::<UpDirection>(character).unwrap().vector();
+
+    // Part of the way after a moment, at the turn speed rather than at once.
+    run(&mut app, 60.0, 0.1);
+    let turned = up(&app).angle_between(Vector::Y);
+    assert!(turned > 0.1 && turned < FRAC_PI_2 - 0.1, "{turned}");
+
+    // All the way after a while, with the character turned along.
+    run(&mut app, 60.0, 1.0);
+    assert!(up(&app).dot(Vector::NEG_Z) > 0.999, "{}", up(&app));
+    let rotation = app.world().get::<Rotation>(character).unwrap().0;
+    assert!((rotation * Vector::Y).dot(Vector::NEG_Z) > 0.999);
+}
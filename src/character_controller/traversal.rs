@@ -0,0 +1,326 @@
+//! Climbing ladders, grabbing ledges and mantling onto them, for reaching spots above the jump
+//! height.
+//!
+//! A [`Climber`] that walks into a [`Ladder`] sensor while moving forward starts climbing it. In
+//! the air, it grabs the top edge of [`Ledge`] colliders in front of it, found with ray casts at
+//! chest height and down onto the top. While in a [`TraversalMode`], the usual movement and
+//! gravity are suspended and the [`MovementInput`] drives the traversal instead: forward and back
+//! climb, jump mantles onto a ledge or lets go of a ladder, and crouch drops down. Mantling only
+//! starts if there is room to stand on top of the ledge.
+
+use avian3d::{math::*, prelude::*};
+use bevy::{ecs::query::Has, prelude::*};
+
+use super::{
+    CharacterController, Grounded, MovementInput, UpDirection, is_penetrating,
+    stance::{Stances, height},
+};
+use crate::simple_scene::game::MainCamera;
+use crate::time::ClockTime;
+
+/// Marks a sensor that can be climbed like a ladder.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Sensor)]
+pub struct Ladder {
+    /// How fast the character climbs, in meters per second.
+    pub climb_speed: Scalar,
+}
+
+impl Default for Ladder {
+    fn default() -> Self {
+        Self { climb_speed: 2.0 }
+    }
+}
+
+/// Marks a collider whose top edge can be grabbed and mantled onto.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Ledge;
+
+/// Lets a character controller climb [`Ladder`]s and grab [`Ledge`]s.
+#[derive(Component, Clone, Debug)]
+pub struct Climber {
+    /// How far above the head a ledge can be and still be grabbed.
+    pub grab_reach: Scalar,
+    /// How far in front of the collider walls are checked for ledges.
+    pub grab_distance: Scalar,
+    /// How long climbing over a ledge takes, in seconds.
+    pub mantle_duration: Scalar,
+    /// How long after letting go nothing is grabbed, so dropping down doesn't catch the same ledge.
+    pub regrab_delay: Scalar,
+    regrab_timer: Scalar,
+}
+
+impl Default for Climber {
+    fn default() -> Self {
+        Self {
+            grab_reach: 0.4,
+            grab_distance: 0.3,
+            mantle_duration: 0.5,
+            regrab_delay: 0.4,
+            regrab_timer: 0.0,
+        }
+    }
+}
+
+/// What a [`Climber`] is doing instead of walking. Removed once it walks again.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+#[component(storage = "SparseSet")]
+pub enum TraversalMode {
+    Climbing {
+        ladder: Entity,
+    },
+    Hanging {
+        /// The point on top of the ledge the character holds on to.
+        ledge_top: Vector,
+        /// Direction along the ground from the character towards the wall.
+        towards_wall: Vector,
+    },
+    Mantling {
+        start: Vector,
+        end: Vector,
+        elapsed: Scalar,
+    },
+}
+
+/// Speed of jumping off a ladder, backwards and up.
+const LADDER_JUMP_SPEED: Scalar = 3.0;
+/// How much of a mantle is spent rising before moving over the edge.
+const MANTLE_RISE_FRACTION: Scalar = 0.6;
+/// Gap kept between the collider and the surfaces it is placed against.
+const SKIN_WIDTH: Scalar = 0.02;
+
+/// Starts, runs and ends the [`TraversalMode`] of [`Climber`]s.
+pub(super) fn traverse(
+    mut commands: Commands,
+    clock: ClockTime,
+    spatial_query: SpatialQuery,
+    ladders: Query<&Ladder>,
+    ledges: Query<(), With<Ledge>>,
+    sensors: Query<(), With<Sensor>>,
+    main_camera: Single<&Transform, With<MainCamera>>,
+    mut controllers: Query<
+        (
+            Entity,
+            &mut Climber,
+            Option<&mut TraversalMode>,
+            &Collider,
+            Option<&Stances>,
+            &Rotation,
+            &UpDirection,
+            &mut Position,
+            &mut LinearVelocity,
+            &mut MovementInput,
+            Has<Grounded>,
+        ),
+        With<CharacterController>,
+    >,
+) {
+    let camera_rotation = main_camera.into_inner().rotation;
+
+    for (
+        entity,
+        mut climber,
+        mode,
+        collider,
+        stances,
+        rotation,
+        up,
+        mut position,
+        mut linear_velocity,
+        mut input,
+        is_grounded,
+    ) in &mut controllers
+    {
+        let delta_time = clock.delta_secs_f64(entity).adjust_precision();
+        climber.regrab_timer = (climber.regrab_timer - delta_time).max(0.0);
+
+        // The character stands along its local Y axis, which its rotation keeps facing up.
+        let up = up.vector();
+        let aabb = collider.aabb(Vector::ZERO, Rotation::default());
+        let half_height = (aabb.max.y - aabb.min.y) / 2.0;
+        let radius = (aabb.max.x - aabb.min.x) / 2.0;
+        let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+
+        // Where forward input is pointing, flattened onto the ground.
+        let forward = (camera_rotation * Vec3::NEG_Z)
+            .adjust_precision()
+            .reject_from_normalized(up)
+            .normalize_or_zero();
+
+        let touching_ladder = || {
+            spatial_query
+                .shape_intersections(collider, position.0, rotation.0, &filter)
+                .into_iter()
+                .find(|hit| ladders.contains(*hit))
+        };
+
+        let Some(mut mode) = mode else {
+            if climber.regrab_timer > 0.0 || input.direction.y <= 0.0 {
+                continue;
+            }
+
+            if let Some(ladder) = touching_ladder() {
+                linear_velocity.0 = Vector::ZERO;
+                commands.entity(entity).insert(TraversalMode::Climbing { ladder });
+            } else if !is_grounded
+                && linear_velocity.dot(up) <= 0.0
+                && let Some((ledge_top, hanging)) = find_ledge(
+                    &spatial_query,
+                    &filter,
+                    &ledges,
+                    position.0,
+                    forward,
+                    up,
+                    half_height,
+                    radius,
+                    &climber,
+                )
+            {
+                position.0 = hanging;
+                linear_velocity.0 = Vector::ZERO;
+                commands.entity(entity).insert(TraversalMode::Hanging {
+                    ledge_top,
+                    towards_wall: forward,
+                });
+            }
+            continue;
+        };
+
+        let jump = std::mem::take(&mut input.jump_pressed);
+        let let_go = |climber: &mut Climber, commands: &mut Commands| {
+            climber.regrab_timer = climber.regrab_delay;
+            commands.entity(entity).remove::<TraversalMode>();
+        };
+
+        match *mode {
+            TraversalMode::Climbing { ladder } => {
+                let Some(climb_speed) = touching_ladder()
+                    .filter(|touching| *touching == ladder)
+                    .and_then(|ladder| ladders.get(ladder).ok())
+                    .map(|ladder| ladder.climb_speed)
+                else {
+                    // Climbed off the top or the bottom. At the top, step forward onto the ledge.
+                    if linear_velocity.dot(up) > 0.0 {
+                        linear_velocity.0 =
+                            forward * LADDER_JUMP_SPEED * 0.5 + up * LADDER_JUMP_SPEED * 0.5;
+                    }
+                    let_go(&mut climber, &mut commands);
+                    continue;
+                };
+
+                if jump {
+                    linear_velocity.0 = -forward * LADDER_JUMP_SPEED + up * LADDER_JUMP_SPEED * 0.5;
+                    let_go(&mut climber, &mut commands);
+                } else if input.crouch_held || (is_grounded && input.direction.y < 0.0) {
+                    let_go(&mut climber, &mut commands);
+                } else {
+                    linear_velocity.0 = up * input.direction.y * climb_speed;
+                }
+            }
+            TraversalMode::Hanging {
+                ledge_top,
+                towards_wall,
+            } => {
+                linear_velocity.0 = Vector::ZERO;
+                if jump || input.direction.y > 0.5 {
+                    // Up over the edge, then far enough in to stand on top.
+                    let end = ledge_top
+                        + towards_wall * (radius + SKIN_WIDTH)
+                        + up * (half_height + SKIN_WIDTH);
+
+                    // Stay hanging if there is no room to stand up there, like under a low
+                    // ceiling.
+                    let mut standing = stances
+                        .map_or(collider, |stances| &stances.stand.collider)
+                        .clone();
+                    let standing_center = end + up * (height(&standing) / 2.0 - half_height);
+                    // Slightly smaller, so the top of the ledge doesn't count as blocking.
+                    standing.set_scale(Vector::ONE * 0.95, 10);
+                    let fits = !is_penetrating(
+                        &spatial_query,
+                        &sensors,
+                        &standing,
+                        rotation.0,
+                        standing_center,
+                        &filter,
+                    );
+
+                    if fits {
+                        *mode = TraversalMode::Mantling {
+                            start: position.0,
+                            end,
+                            elapsed: 0.0,
+                        };
+                    }
+                } else if input.crouch_held {
+                    let_go(&mut climber, &mut commands);
+                }
+            }
+            TraversalMode::Mantling {
+                start,
+                end,
+                ref mut elapsed,
+            } => {
+                *elapsed += delta_time;
+                let progress = (*elapsed / climber.mantle_duration.max(delta_time)).min(1.0);
+                let rise = (progress / MANTLE_RISE_FRACTION).min(1.0);
+                let over =
+                    ((progress - MANTLE_RISE_FRACTION) / (1.0 - MANTLE_RISE_FRACTION)).max(0.0);
+
+                // Over the edge along the ground, and up by the rise.
+                let along = start.lerp(end, over);
+                position.0 = along + up * ((end - start).dot(up) * rise - (along - start).dot(up));
+                linear_velocity.0 = Vector::ZERO;
+
+                if progress >= 1.0 {
+                    commands.entity(entity).remove::<TraversalMode>();
+                }
+            }
+        }
+    }
+}
+
+/// Looks for a [`Ledge`] in front of a character, returning the top of the ledge and where the
+/// character hangs from it.
+fn find_ledge(
+    spatial_query: &SpatialQuery,
+    filter: &SpatialQueryFilter,
+    ledges: &Query<(), With<Ledge>>,
+    position: Vector,
+    forward: Vector,
+    up: Vector,
+    half_height: Scalar,
+    radius: Scalar,
+    climber: &Climber,
+) -> Option<(Vector, Vector)> {
+    let forward_dir = Dir3::new(forward.f32()).ok()?;
+
+    // The wall in front of the chest.
+    let chest = position + up * half_height * 0.5;
+    let wall = spatial_query.cast_ray(
+        chest,
+        forward_dir,
+        radius + climber.grab_distance,
+        true,
+        filter,
+    )?;
+    if !ledges.contains(wall.entity) {
+        return None;
+    }
+    let wall_point = chest + forward * wall.distance;
+
+    // The top of the wall, searched from above the reach of the hands down to the chest.
+    let down = Dir3::new(-up.f32()).ok()?;
+    let above = half_height * 0.5 + climber.grab_reach;
+    let probe = wall_point + up * above + forward * (SKIN_WIDTH * 5.0);
+    let top = spatial_query.cast_ray(probe, down, above, true, filter)?;
+    if top.entity != wall.entity || top.normal.dot(up) < 0.7 {
+        return None;
+    }
+    let ledge_top = probe - up * top.distance;
+
+    // Hang with the hands on the edge and the body just off the wall.
+    let hanging = wall_point + up * ((ledge_top - wall_point).dot(up) - half_height * 0.8)
+        - forward * (radius + SKIN_WIDTH);
+    Some((ledge_top, hanging))
+}
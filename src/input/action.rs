@@ -0,0 +1,171 @@
+//! Logical actions and axes, and their state for the current frame.
+//!
+//! Systems ask [`ActionState`] about a [`GameAction`] or [`GameAxis`] instead of reading keys,
+//! mouse buttons or gamepads directly. The physical bindings live in the
+//! [`InputMap`](super::InputMap).
+
+use bevy::{
+    input::{InputSystem, mouse::AccumulatedMouseScroll},
+    platform::collections::HashSet,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::map::InputMap;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ActionState>()
+        .add_systems(PreUpdate, update_action_state.after(InputSystem));
+}
+
+/// Something the player can do by pressing a binding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+pub enum GameAction {
+    Jump,
+    /// Held to crouch.
+    Crouch,
+    /// Pressed to lie down or get back up.
+    Prone,
+    /// Held to sprint while stamina lasts.
+    Sprint,
+    Refill,
+    CycleNozzle,
+    ToggleCursorGrab,
+    ToggleCameraState,
+    /// Moves the third person camera over the other shoulder.
+    SwapShoulder,
+    /// Plays the camera path, or stops it if it is playing.
+    PlayCinematic,
+    /// Detaches the camera from the player to fly around freely, or reattaches it.
+    ToggleFreeFly,
+    /// Switches to the next level.
+    NextLevel,
+    ToggleDiagnosticsUi,
+    TogglePhysicsPause,
+    StepPhysics,
+    ToggleUiDebug,
+}
+
+/// An analog value in `-1..=1` the player controls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+pub enum GameAxis {
+    /// Strafing, positive to the right.
+    MoveX,
+    /// Walking, positive forward.
+    MoveY,
+    /// How hard the spray can nozzle is pressed, in `0..=1`.
+    SprayPressure,
+    /// Zooming the third person camera in steps, positive towards the character.
+    ///
+    /// Read every frame, so it suits the mouse wheel more than held buttons.
+    Zoom,
+}
+
+/// The state of every [`GameAction`] and [`GameAxis`] this frame.
+#[derive(Resource, Debug, Default)]
+pub struct ActionState {
+    pressed: HashSet<GameAction>,
+    just_pressed: HashSet<GameAction>,
+    just_released: HashSet<GameAction>,
+    axes: Vec<(GameAxis, f32)>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: GameAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: GameAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: GameAction) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// The value of an axis, or zero if nothing drives it.
+    pub fn axis(&self, axis: GameAxis) -> f32 {
+        self.axes
+            .iter()
+            .find(|(known, _)| *known == axis)
+            .map_or(0.0, |(_, value)| *value)
+    }
+
+    /// Two axes as a vector, clamped to unit length.
+    pub fn axis_pair(&self, x: GameAxis, y: GameAxis) -> Vec2 {
+        Vec2::new(self.axis(x), self.axis(y)).clamp_length_max(1.0)
+    }
+
+    /// Presses an action for this frame, as if one of its bindings was held.
+    ///
+    /// Useful for scripted input, since the state is rebuilt from the bindings every frame.
+    pub fn press(&mut self, action: GameAction) {
+        if self.pressed.insert(action) {
+            self.just_pressed.insert(action);
+        }
+    }
+
+    /// Overrides an axis for this frame.
+    pub fn set_axis(&mut self, axis: GameAxis, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        match self.axes.iter_mut().find(|(known, _)| *known == axis) {
+            Some((_, known)) => *known = value,
+            None => self.axes.push((axis, value)),
+        }
+    }
+}
+
+/// Evaluates the [`InputMap`] against the raw input of this frame.
+fn update_action_state(
+    input_map: Res<InputMap>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mouse_scroll: Res<AccumulatedMouseScroll>,
+    gamepads: Query<&Gamepad>,
+    mut state: ResMut<ActionState>,
+) {
+    let was_pressed = std::mem::take(&mut state.pressed);
+    state.just_pressed.clear();
+    state.just_released.clear();
+
+    for (action, bindings) in &input_map.actions {
+        if bindings
+            .iter()
+            .any(|binding| binding.pressed(&keys, &mouse_buttons, &gamepads))
+        {
+            state.pressed.insert(*action);
+        }
+    }
+
+    // Edges come from the action state rather than from the bindings, so a chord is just pressed
+    // on the frame its last key goes down.
+    let state = state.as_mut();
+    state
+        .just_pressed
+        .extend(state.pressed.difference(&was_pressed).copied());
+    state
+        .just_released
+        .extend(was_pressed.difference(&state.pressed).copied());
+
+    state.axes = input_map
+        .axes
+        .iter()
+        .map(|(axis, bindings)| {
+            let value: f32 = bindings
+                .iter()
+                .map(|binding| binding.value(&keys, &mouse_buttons, &mouse_scroll, &gamepads))
+                .sum();
+            (*axis, value.clamp(-1.0, 1.0))
+        })
+        .collect();
+}
+
+/// Run condition that is true on the frame `action` is pressed.
+pub fn action_just_pressed(action: GameAction) -> impl FnMut(Res<ActionState>) -> bool + Clone {
+    move |state: Res<ActionState>| state.just_pressed(action)
+}
+
+/// Run condition that is true on the frame `action` is released.
+pub fn action_just_released(action: GameAction) -> impl FnMut(Res<ActionState>) -> bool + Clone {
+    move |state: Res<ActionState>| state.just_released(action)
+}
@@ -0,0 +1,400 @@
+//! Bindings from physical inputs to logical actions.
+//!
+//! The bindings live in `assets/settings/controls.input.ron`. The [`InputMap`] resource starts
+//! out with the copy of that file built into the game, and is replaced by the file on disk once
+//! that has loaded. Edits to the file are picked up while the game runs when the `file_watcher`
+//! feature is enabled.
+//!
+//! The resource can be rebound from code at any time. Loading the file keeps those changes: only
+//! actions and axes whose bindings are still the ones from the last load take the new bindings.
+
+use std::{collections::HashMap, hash::Hash};
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    input::mouse::{AccumulatedMouseScroll, MouseScrollUnit},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use super::action::{GameAction, GameAxis};
+use crate::bevy_starter::ron_asset::{RonLoaderError, read_ron};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<InputMap>()
+        .add_systems(
+            Startup,
+            load_input_map.run_if(resource_exists::<Assets<InputMap>>),
+        )
+        .add_systems(
+            PreUpdate,
+            apply_loaded_input_map.run_if(resource_exists::<InputMapHandle>),
+        );
+}
+
+/// Registers the [`InputMap`] asset, once all plugins are built so the [`AssetPlugin`] can be
+/// added in any order. Without one the game keeps the built-in bindings.
+pub(super) fn register_asset(app: &mut App) {
+    if app.world().contains_resource::<AssetServer>() {
+        app.init_asset::<InputMap>()
+            .register_asset_loader(InputMapLoader);
+    }
+}
+
+/// Where the bindings are loaded from, relative to `assets/`.
+pub const INPUT_MAP_PATH: &str = "settings/controls.input.ron";
+
+/// The bindings built into the game, used until [`INPUT_MAP_PATH`] has loaded.
+const BUILT_IN_BINDINGS: &str = include_str!("../../assets/settings/controls.input.ron");
+
+/// A physical input that can trigger a [`GameAction`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// A button on any connected gamepad.
+    Gamepad(GamepadButton),
+    /// Held while all of the bindings are held, such as `Ctrl + S`.
+    Chord(Vec<InputBinding>),
+}
+
+impl InputBinding {
+    pub fn pressed(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        mouse_buttons: &ButtonInput<MouseButton>,
+        gamepads: &Query<&Gamepad>,
+    ) -> bool {
+        match self {
+            InputBinding::Key(key) => keys.pressed(*key),
+            InputBinding::Mouse(button) => mouse_buttons.pressed(*button),
+            InputBinding::Gamepad(button) => {
+                gamepads.iter().any(|gamepad| gamepad.pressed(*button))
+            }
+            InputBinding::Chord(bindings) => {
+                !bindings.is_empty()
+                    && bindings
+                        .iter()
+                        .all(|binding| binding.pressed(keys, mouse_buttons, gamepads))
+            }
+        }
+    }
+}
+
+/// A physical input that drives a [`GameAxis`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AxisBinding {
+    /// -1 while `negative` is held and 1 while `positive` is held.
+    Buttons {
+        negative: Option<InputBinding>,
+        positive: Option<InputBinding>,
+    },
+    /// A stick axis of any connected gamepad.
+    GamepadAxis {
+        axis: GamepadAxis,
+        deadzone: f32,
+        #[serde(default)]
+        inverted: bool,
+    },
+    /// The analog value of a gamepad button, such as a trigger.
+    GamepadButton { button: GamepadButton, deadzone: f32 },
+    /// Scrolling the mouse wheel this frame, in lines scaled by `sensitivity`.
+    MouseWheel { sensitivity: f32 },
+}
+
+impl AxisBinding {
+    pub fn value(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        mouse_buttons: &ButtonInput<MouseButton>,
+        mouse_scroll: &AccumulatedMouseScroll,
+        gamepads: &Query<&Gamepad>,
+    ) -> f32 {
+        match self {
+            AxisBinding::Buttons { negative, positive } => {
+                let held = |binding: &Option<InputBinding>| {
+                    binding
+                        .as_ref()
+                        .is_some_and(|binding| binding.pressed(keys, mouse_buttons, gamepads))
+                };
+                held(positive) as i8 as f32 - held(negative) as i8 as f32
+            }
+            AxisBinding::GamepadAxis {
+                axis,
+                deadzone,
+                inverted,
+            } => {
+                let value = strongest(gamepads.iter().filter_map(|gamepad| gamepad.get(*axis)));
+                let value = apply_deadzone(value, *deadzone);
+                if *inverted { -value } else { value }
+            }
+            AxisBinding::GamepadButton { button, deadzone } => apply_deadzone(
+                strongest(gamepads.iter().filter_map(|gamepad| gamepad.get(*button))),
+                *deadzone,
+            ),
+            AxisBinding::MouseWheel { sensitivity } => {
+                let lines = match mouse_scroll.unit {
+                    MouseScrollUnit::Line => mouse_scroll.delta.y,
+                    // Roughly one line of a typical touchpad or smooth scrolling mouse.
+                    MouseScrollUnit::Pixel => mouse_scroll.delta.y / 16.0,
+                };
+                lines * sensitivity
+            }
+        }
+    }
+}
+
+/// The value furthest from zero, so an idle gamepad doesn't cancel out an active one.
+fn strongest(values: impl Iterator<Item = f32>) -> f32 {
+    values.fold(0.0, |strongest, value| {
+        if value.abs() > strongest.abs() { value } else { strongest }
+    })
+}
+
+/// Zeroes values inside the deadzone and rescales the rest to still reach the full range.
+fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let deadzone = deadzone.clamp(0.0, 0.99);
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+    }
+}
+
+/// Maps logical actions and axes to physical bindings.
+#[derive(Resource, Asset, TypePath, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    pub actions: HashMap<GameAction, Vec<InputBinding>>,
+    pub axes: HashMap<GameAxis, Vec<AxisBinding>>,
+}
+
+impl InputMap {
+    /// Adds a binding for `action`, keeping the existing ones.
+    pub fn bind(&mut self, action: GameAction, binding: InputBinding) -> &mut Self {
+        let bindings = self.actions.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+
+    /// Replaces all bindings of `action` with `binding`.
+    pub fn rebind(&mut self, action: GameAction, binding: InputBinding) -> &mut Self {
+        self.actions.insert(action, vec![binding]);
+        self
+    }
+
+    /// Removes all bindings of `action`.
+    pub fn unbind(&mut self, action: GameAction) -> &mut Self {
+        self.actions.remove(&action);
+        self
+    }
+
+    /// Adds a binding for `axis`, keeping the existing ones.
+    pub fn bind_axis(&mut self, axis: GameAxis, binding: AxisBinding) -> &mut Self {
+        self.axes.entry(axis).or_default().push(binding);
+        self
+    }
+
+    /// The bindings of `action`.
+    pub fn bindings(&self, action: GameAction) -> &[InputBinding] {
+        self.actions.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// `loaded`, keeping the bindings of this map that were changed since `previous` was loaded.
+    fn reloaded(&self, previous: &InputMap, loaded: &InputMap) -> InputMap {
+        let mut merged = loaded.clone();
+        keep_changes(&mut merged.actions, &previous.actions, &self.actions);
+        keep_changes(&mut merged.axes, &previous.axes, &self.axes);
+        merged
+    }
+}
+
+/// Copies the entries of `current` that differ from `previous`, or were removed, into `merged`.
+fn keep_changes<K: Copy + Eq + Hash, V: Clone + PartialEq>(
+    merged: &mut HashMap<K, V>,
+    previous: &HashMap<K, V>,
+    current: &HashMap<K, V>,
+) {
+    for key in previous.keys().chain(current.keys()) {
+        match current.get(key) {
+            Some(value) if previous.get(key) != Some(value) => {
+                merged.insert(*key, value.clone());
+            }
+            None if previous.contains_key(key) => {
+                merged.remove(key);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        ron::de::from_str(BUILT_IN_BINDINGS).expect("the built-in bindings are valid")
+    }
+}
+
+/// Loads an [`InputMap`] from a RON file.
+#[derive(Default)]
+struct InputMapLoader;
+
+impl AssetLoader for InputMapLoader {
+    type Asset = InputMap;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        read_ron(reader).await
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["input.ron"]
+    }
+}
+
+/// Keeps the loaded input map alive so it can be hot reloaded, along with the bindings it last
+/// applied.
+#[derive(Resource)]
+struct InputMapHandle {
+    handle: Handle<InputMap>,
+    applied: InputMap,
+}
+
+fn load_input_map(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(InputMapHandle {
+        handle: asset_server.load(INPUT_MAP_PATH),
+        applied: InputMap::default(),
+    });
+}
+
+/// Applies the file to the [`InputMap`] resource whenever it finishes loading or changes on disk,
+/// keeping bindings changed from code.
+fn apply_loaded_input_map(
+    mut events: EventReader<AssetEvent<InputMap>>,
+    mut handle: ResMut<InputMapHandle>,
+    assets: Res<Assets<InputMap>>,
+    mut input_map: ResMut<InputMap>,
+) {
+    for event in events.read() {
+        if (event.is_loaded_with_dependencies(&handle.handle) || event.is_modified(&handle.handle))
+            && let Some(loaded) = assets.get(&handle.handle)
+        {
+            *input_map = input_map.reloaded(&handle.applied, loaded);
+            handle.applied = loaded.clone();
+            info!("Loaded input bindings from {INPUT_MAP_PATH}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// Whether `binding` is held while `keys` are.
+    fn pressed(binding: InputBinding, keys: &[KeyCode]) -> bool {
+        let mut key_input = ButtonInput::default();
+        for key in keys {
+            key_input.press(*key);
+        }
+        World::new()
+            .run_system_once(move |gamepads: Query<&Gamepad>| {
+                binding.pressed(&key_input, &ButtonInput::default(), &gamepads)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn chords_are_held_while_all_of_their_bindings_are() {
+        use InputBinding::{Chord, Key};
+
+        let save = Chord(vec![Key(KeyCode::ControlLeft), Key(KeyCode::KeyS)]);
+        assert!(pressed(
+            save.clone(),
+            &[KeyCode::ControlLeft, KeyCode::KeyS]
+        ));
+        assert!(pressed(
+            save.clone(),
+            &[KeyCode::ShiftLeft, KeyCode::ControlLeft, KeyCode::KeyS]
+        ));
+        assert!(!pressed(save.clone(), &[KeyCode::KeyS]));
+        assert!(!pressed(save, &[]));
+
+        // Chords nest, and an empty one is never held.
+        let nested = Chord(vec![
+            Key(KeyCode::AltLeft),
+            Chord(vec![Key(KeyCode::ControlLeft), Key(KeyCode::KeyS)]),
+        ]);
+        assert!(pressed(
+            nested.clone(),
+            &[KeyCode::AltLeft, KeyCode::ControlLeft, KeyCode::KeyS]
+        ));
+        assert!(!pressed(nested, &[KeyCode::AltLeft, KeyCode::KeyS]));
+        assert!(!pressed(Chord(Vec::new()), &[KeyCode::KeyS]));
+    }
+
+    #[test]
+    fn deadzones_zero_small_values_and_keep_the_full_range() {
+        assert_eq!(apply_deadzone(0.05, 0.1), 0.0);
+        assert_eq!(apply_deadzone(-0.1, 0.1), 0.0);
+        assert_eq!(apply_deadzone(1.0, 0.1), 1.0);
+        assert_eq!(apply_deadzone(-1.0, 0.1), -1.0);
+        assert!((apply_deadzone(0.55, 0.1) - 0.5).abs() < 1e-6);
+        assert!((apply_deadzone(-0.55, 0.1) + 0.5).abs() < 1e-6);
+        // Out of range deadzones still leave values reachable.
+        assert_eq!(apply_deadzone(0.5, -1.0), 0.5);
+        assert_eq!(apply_deadzone(1.0, 2.0), 1.0);
+
+        assert_eq!(strongest([0.2, -0.7, 0.5].into_iter()), -0.7);
+        assert_eq!(strongest(std::iter::empty()), 0.0);
+    }
+
+    #[test]
+    fn shipped_bindings_round_trip_through_ron() {
+        let bindings = InputMap::default();
+        assert!(
+            bindings
+                .bindings(GameAction::Jump)
+                .contains(&InputBinding::Key(KeyCode::Space))
+        );
+        assert!(bindings.axes.contains_key(&GameAxis::SprayPressure));
+
+        let serialized =
+            ron::ser::to_string_pretty(&bindings, ron::ser::PrettyConfig::default()).unwrap();
+        let parsed: InputMap = ron::de::from_str(&serialized).unwrap();
+        assert_eq!(parsed, bindings);
+    }
+
+    #[test]
+    fn reloading_keeps_bindings_changed_from_code() {
+        let previous = InputMap::default();
+        let mut current = previous.clone();
+        current
+            .rebind(GameAction::Jump, InputBinding::Key(KeyCode::KeyJ))
+            .unbind(GameAction::Refill);
+
+        let mut loaded = previous.clone();
+        loaded
+            .rebind(GameAction::Jump, InputBinding::Key(KeyCode::KeyK))
+            .rebind(GameAction::Sprint, InputBinding::Key(KeyCode::KeyL));
+
+        let reloaded = current.reloaded(&previous, &loaded);
+        assert_eq!(
+            reloaded.bindings(GameAction::Jump),
+            [InputBinding::Key(KeyCode::KeyJ)]
+        );
+        assert!(reloaded.bindings(GameAction::Refill).is_empty());
+        assert_eq!(
+            reloaded.bindings(GameAction::Sprint),
+            [InputBinding::Key(KeyCode::KeyL)]
+        );
+        assert_eq!(reloaded.axes, loaded.axes);
+    }
+}
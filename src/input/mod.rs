@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+pub mod action;
+pub mod map;
+
+pub use action::{ActionState, GameAction, GameAxis, action_just_pressed, action_just_released};
+pub use map::{AxisBinding, InputBinding, InputMap};
+
+pub fn add_all_plugins(app: &mut App) {
+    app.add_plugins(map::plugin);
+    app.add_plugins(action::plugin);
+}
+
+pub struct InputMapPlugin;
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        add_all_plugins(app);
+    }
+
+    fn finish(&self, app: &mut App) {
+        map::register_asset(app);
+    }
+}
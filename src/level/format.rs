@@ -0,0 +1,319 @@
+//! The level file format: a RON description of everything a level is made of.
+//!
+//! Levels are `.level.ron` files in `assets/levels/`. See `simple.level.ron` for an example of
+//! every kind of object.
+
+use avian3d::prelude::{Collider, RigidBody};
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    math::bounding::{Aabb3d, Bounded3d},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::bevy_starter::ron_asset::{RonLoaderError, read_ron};
+use crate::spray::canvas::{MAX_CANVAS_TEXELS, PaintUvLayout};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_asset::<Level>().register_asset_loader(LevelLoader);
+}
+
+/// A level, loaded from a `.level.ron` file.
+#[derive(Asset, TypePath, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Level {
+    #[serde(default)]
+    pub name: String,
+    /// Where the character can start. The first one is used when entering the level.
+    #[serde(default)]
+    pub spawn_points: Vec<SpawnPointDefinition>,
+    /// Characters falling below this height respawn. Defaults to the [`KillPlane`] default.
+    ///
+    /// [`KillPlane`]: super::respawn::KillPlane
+    #[serde(default)]
+    pub kill_height: Option<f32>,
+    #[serde(default)]
+    pub lights: Vec<LightDefinition>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDefinition>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnPointDefinition {
+    pub position: Vec3,
+    /// Where the spawn point faces. Only turned around the vertical.
+    #[serde(default)]
+    pub look_at: Option<Vec3>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LightDefinition {
+    Point {
+        position: Vec3,
+        intensity: f32,
+        range: f32,
+        #[serde(default)]
+        shadows: bool,
+        #[serde(default = "white")]
+        color: [u8; 4],
+    },
+    Directional {
+        /// The direction the light shines in.
+        direction: Vec3,
+        illuminance: f32,
+        #[serde(default)]
+        shadows: bool,
+        #[serde(default = "white")]
+        color: [u8; 4],
+    },
+}
+
+fn white() -> [u8; 4] {
+    [255; 4]
+}
+
+/// A shape for the mesh or the collider of an object, centered on it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ShapeDefinition {
+    /// A box with the given full size.
+    Cuboid {
+        size: Vec3,
+    },
+    Cylinder {
+        radius: f32,
+        height: f32,
+    },
+    Sphere {
+        radius: f32,
+    },
+    Capsule {
+        radius: f32,
+        length: f32,
+    },
+}
+
+impl ShapeDefinition {
+    pub fn mesh(&self) -> Mesh {
+        match *self {
+            ShapeDefinition::Cuboid { size } => Cuboid::from_size(size).into(),
+            ShapeDefinition::Cylinder { radius, height } => Cylinder::new(radius, height).into(),
+            ShapeDefinition::Sphere { radius } => Sphere::new(radius).into(),
+            ShapeDefinition::Capsule { radius, length } => Capsule3d::new(radius, length).into(),
+        }
+    }
+
+    pub fn collider(&self) -> Collider {
+        match *self {
+            ShapeDefinition::Cuboid { size } => Collider::cuboid(size.x, size.y, size.z),
+            ShapeDefinition::Cylinder { radius, height } => Collider::cylinder(radius, height),
+            ShapeDefinition::Sphere { radius } => Collider::sphere(radius),
+            ShapeDefinition::Capsule { radius, length } => Collider::capsule(radius, length),
+        }
+    }
+
+    /// The bounds of this shape when placed at `isometry`.
+    pub fn aabb(&self, isometry: Isometry3d) -> Aabb3d {
+        match *self {
+            ShapeDefinition::Cuboid { size } => Cuboid::from_size(size).aabb_3d(isometry),
+            ShapeDefinition::Cylinder { radius, height } => {
+                Cylinder::new(radius, height).aabb_3d(isometry)
+            }
+            ShapeDefinition::Sphere { radius } => Sphere::new(radius).aabb_3d(isometry),
+            ShapeDefinition::Capsule { radius, length } => {
+                Capsule3d::new(radius, length).aabb_3d(isometry)
+            }
+        }
+    }
+
+    /// How a paint canvas is laid out on this shape, if it can be painted on.
+    pub fn paint_layout(&self) -> Option<PaintUvLayout> {
+        match *self {
+            ShapeDefinition::Cuboid { size } => Some(PaintUvLayout::Cuboid { size }),
+            ShapeDefinition::Cylinder { radius, height } => {
+                Some(PaintUvLayout::Cylinder { radius, height })
+            }
+            ShapeDefinition::Sphere { .. } | ShapeDefinition::Capsule { .. } => None,
+        }
+    }
+}
+
+/// The [`RigidBody`] type of an object.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyDefinition {
+    #[default]
+    Static,
+    Kinematic,
+    Dynamic,
+}
+
+impl From<BodyDefinition> for RigidBody {
+    fn from(body: BodyDefinition) -> Self {
+        match body {
+            BodyDefinition::Static => RigidBody::Static,
+            BodyDefinition::Kinematic => RigidBody::Kinematic,
+            BodyDefinition::Dynamic => RigidBody::Dynamic,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MaterialDefinition {
+    /// sRGB color with alpha.
+    pub color: [u8; 4],
+    /// Blend with what's behind, for see-through materials.
+    #[serde(default)]
+    pub transparent: bool,
+}
+
+impl Default for MaterialDefinition {
+    fn default() -> Self {
+        Self {
+            color: white(),
+            transparent: false,
+        }
+    }
+}
+
+/// A coverage goal of a paintable object.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CoverageGoalDefinition {
+    /// The fraction of the surface that has to be covered, in `0..=1`.
+    pub fraction: f32,
+    /// Only these faces of the paint layout count. All faces count if `None`.
+    #[serde(default)]
+    pub faces: Option<Vec<usize>>,
+}
+
+/// Gameplay roles an object can have, on top of its shape and body.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BehaviorDefinition {
+    Block,
+    /// Kept in frame by the static view.
+    CameraTarget,
+    /// Its top edges can be grabbed.
+    Ledge,
+    /// Can be climbed. Makes the collider a sensor.
+    Ladder,
+    /// Makes the collider a sensor filled with water.
+    Water,
+    /// Makes the collider a sensor filled with mud.
+    Mud,
+    /// Makes the collider a sensor that respawns characters touching it.
+    OutOfBounds,
+    /// Makes the collider a sensor pulling towards its center.
+    SphericalGravity {
+        strength: f32,
+    },
+}
+
+/// Anything placed in a level.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ObjectDefinition {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub position: Vec3,
+    #[serde(default)]
+    pub rotation: Quat,
+    /// The shape that is rendered. Invisible if `None`.
+    #[serde(default)]
+    pub mesh: Option<ShapeDefinition>,
+    #[serde(default)]
+    pub material: MaterialDefinition,
+    /// The shape that collides. Not physical at all if `None`.
+    #[serde(default)]
+    pub collider: Option<ShapeDefinition>,
+    #[serde(default)]
+    pub body: BodyDefinition,
+    #[serde(default)]
+    pub angular_velocity: Vec3,
+    /// The resolution of the paint canvas, if the object can be painted on.
+    #[serde(default)]
+    pub paintable: Option<UVec2>,
+    #[serde(default)]
+    pub coverage_goals: Vec<CoverageGoalDefinition>,
+    #[serde(default)]
+    pub behaviors: Vec<BehaviorDefinition>,
+}
+
+impl ObjectDefinition {
+    /// The bounds of the collider, or of the mesh without one, within the level.
+    pub fn aabb(&self) -> Option<Aabb3d> {
+        let shape = self.collider.or(self.mesh)?;
+        Some(shape.aabb(Isometry3d::new(self.position, self.rotation)))
+    }
+}
+
+impl Level {
+    /// Rejects canvases above [`MAX_CANVAS_TEXELS`], instead of allocating whatever the file asks
+    /// for.
+    fn check_canvases(&self) -> Result<(), String> {
+        for (index, object) in self.objects.iter().enumerate() {
+            let Some(size) = object.paintable else {
+                continue;
+            };
+            if size.x as u64 * size.y as u64 > MAX_CANVAS_TEXELS as u64 {
+                return Err(format!(
+                    "the canvas of object {index} ({}) is {}x{} texels, more than the {} allowed",
+                    object.name.as_deref().unwrap_or("unnamed"),
+                    size.x,
+                    size.y,
+                    MAX_CANVAS_TEXELS
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads a [`Level`] from a RON file.
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = RonLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let level: Level = read_ron(reader).await?;
+        level.check_canvases().map_err(RonLoaderError::Invalid)?;
+        Ok(level)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_levels_have_canvases_within_budget() {
+        for (file, source) in [
+            ("arena", include_str!("../../assets/levels/arena.level.ron")),
+            (
+                "simple",
+                include_str!("../../assets/levels/simple.level.ron"),
+            ),
+        ] {
+            let level: Level =
+                ron::from_str(source).unwrap_or_else(|error| panic!("{file}: {error}"));
+            assert_eq!(level.check_canvases(), Ok(()), "{file}");
+        }
+    }
+
+    #[test]
+    fn oversized_canvases_are_rejected() {
+        let level: Level = ron::from_str(
+            "(objects: [(position: (0.0, 0.0, 0.0), paintable: Some((65536, 65536)))])",
+        )
+        .unwrap();
+        assert!(level.check_canvases().is_err());
+    }
+}
@@ -0,0 +1,185 @@
+//! Levels loaded from files instead of being built in code.
+//!
+//! All levels listed in [`LevelAssets`] are loaded up front through [`LoadResource`], and the
+//! [`CurrentLevel`] is spawned once they are ready. Switching levels despawns every
+//! [`LevelEntity`] and spawns the next level in their place, and with the `file_watcher` feature
+//! editing the file of the current level respawns it right away.
+//!
+//! After the levels from files comes a city generated from the [`UrbanSettings`], which is
+//! generated again whenever they change.
+
+use bevy::prelude::*;
+
+use crate::bevy_starter::{asset_tracking::LoadResource, prelude::GameRng};
+use crate::input::{ActionState, GameAction};
+use crate::simple_scene::game::MainCharacter;
+use respawn::{KillPlane, RespawnCause, RespawnRequested};
+
+pub mod format;
+pub mod respawn;
+pub mod spawn;
+pub mod urban;
+
+pub use format::Level;
+pub use urban::UrbanSettings;
+
+pub fn add_all_plugins(app: &mut App) {
+    app.add_plugins((format::plugin, respawn::plugin));
+    app.init_resource::<CurrentLevel>();
+    let urban = match app.world_mut().get_resource_mut::<GameRng>() {
+        Some(mut rng) => UrbanSettings::from_rng(&mut rng),
+        None => UrbanSettings::default(),
+    };
+    app.insert_resource(urban);
+
+    if app.world().contains_resource::<AssetServer>() {
+        app.load_resource::<LevelAssets>();
+        app.add_systems(
+            Update,
+            (
+                switch_level,
+                regenerate_urban_level.run_if(resource_changed::<UrbanSettings>),
+                sync_level,
+            )
+                .chain()
+                .run_if(resource_exists::<LevelAssets>)
+                .before(respawn::respawn_characters),
+        );
+    }
+}
+
+pub struct LevelPlugin;
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        add_all_plugins(app);
+    }
+}
+
+/// The levels that can be played, in the order they are switched through.
+pub const LEVEL_PATHS: &[&str] = &["levels/simple.level.ron", "levels/arena.level.ron"];
+
+/// Handles to all levels, inserted as a resource once they have loaded.
+#[derive(Resource, Asset, Clone, TypePath)]
+pub struct LevelAssets {
+    /// The levels from [`LEVEL_PATHS`], followed by the generated city.
+    #[dependency]
+    pub levels: Vec<Handle<Level>>,
+    /// The generated city, also the last of the `levels`.
+    pub urban: Handle<Level>,
+}
+
+impl FromWorld for LevelAssets {
+    fn from_world(world: &mut World) -> Self {
+        let city = world
+            .get_resource::<UrbanSettings>()
+            .cloned()
+            .unwrap_or_default()
+            .generate();
+        let assets = world.resource::<AssetServer>();
+        let urban = assets.add(city);
+        let mut levels: Vec<_> = LEVEL_PATHS.iter().map(|path| assets.load(*path)).collect();
+        levels.push(urban.clone());
+        Self { levels, urban }
+    }
+}
+
+/// Which of the [`LevelAssets`] is played.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct CurrentLevel {
+    pub index: usize,
+    /// The level that is spawned right now.
+    spawned: Option<usize>,
+}
+
+/// Marks entities that belong to the spawned level, and are despawned with it.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct LevelEntity;
+
+/// A place the character can start at.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct SpawnPoint {
+    /// Its place among the spawn points of the level. The lowest one is used first.
+    pub index: usize,
+    /// Where the camera looks after spawning here, which unlike the transform can be tilted.
+    pub view: Quat,
+}
+
+/// The block wall of the simple level.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Block;
+
+fn switch_level(
+    action_state: Res<ActionState>,
+    levels: Res<LevelAssets>,
+    mut current_level: ResMut<CurrentLevel>,
+) {
+    if action_state.just_pressed(GameAction::NextLevel) {
+        current_level.index = (current_level.index + 1) % levels.levels.len().max(1);
+    }
+}
+
+/// Replaces the generated city with one for the current [`UrbanSettings`].
+fn regenerate_urban_level(
+    settings: Res<UrbanSettings>,
+    levels: Res<LevelAssets>,
+    mut level_definitions: ResMut<Assets<Level>>,
+) {
+    // The city was just generated with these settings when the levels were loaded.
+    if settings.is_added() {
+        return;
+    }
+    if let Some(city) = level_definitions.get_mut(&levels.urban) {
+        *city = settings.generate();
+    }
+}
+
+/// Spawns the [`CurrentLevel`] when it changes, or when its file changes.
+fn sync_level(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<Level>>,
+    levels: Res<LevelAssets>,
+    level_definitions: Res<Assets<Level>>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    level_entities: Query<Entity, With<LevelEntity>>,
+    mut kill_plane: ResMut<KillPlane>,
+    mut respawn_requests: EventWriter<RespawnRequested>,
+    main_character: Query<Entity, With<MainCharacter>>,
+) {
+    let Some(handle) = levels.levels.get(current_level.index) else {
+        return;
+    };
+    let switched = current_level.spawned != Some(current_level.index);
+    let reloaded = asset_events
+        .read()
+        .filter(|event| event.is_modified(handle.id()))
+        .count()
+        > 0;
+    if !switched && !reloaded {
+        return;
+    }
+    let Some(level) = level_definitions.get(handle) else {
+        return;
+    };
+
+    for entity in &level_entities {
+        commands.entity(entity).despawn();
+    }
+    spawn::spawn_level(&mut commands, &mut meshes, &mut materials, level);
+    *kill_plane = level
+        .kill_height
+        .map_or_else(KillPlane::default, |height| KillPlane { height });
+    current_level.spawned = Some(current_level.index);
+    info!("Spawned level {:?}", level.name);
+
+    // Entering a level starts at its first spawn point. Reloading it leaves the character be.
+    if switched {
+        for entity in &main_character {
+            respawn_requests.write(RespawnRequested {
+                entity,
+                cause: RespawnCause::LevelEntered,
+            });
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Respawning character controllers that left the world.
+//!
+//! A character that falls below the [`KillPlane`] or touches an [`OutOfBounds`] volume is sent
+//! back to a [`SpawnPoint`]. Other plugins can send [`RespawnRequested`] to respawn a character
+//! for their own reasons, and observe [`Respawned`] to react to it, like resetting a score.
+
+use avian3d::{math::*, prelude::*};
+use bevy::{platform::collections::HashSet, prelude::*};
+
+use super::SpawnPoint;
+use crate::camera::{first_person::EyeOffset, transition::CameraTransition};
+use crate::character_controller::{
+    CharacterController, Grounded, GroundedOn, JumpState, MovementInput, PlatformVelocity, Sprint,
+    Stance, Stances, Submerged, Swimming, TraversalMode, UpDirection, find_safe_position,
+};
+use crate::simple_scene::game::{CameraState, MainCamera, MainCharacter};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<KillPlane>()
+        .add_event::<RespawnRequested>()
+        .add_event::<Respawned>()
+        .add_systems(
+            Update,
+            (detect_out_of_world, respawn_characters, log_respawns).chain(),
+        );
+}
+
+/// Where a character respawns without any [`SpawnPoint`].
+const FALLBACK_SPAWN_POSITION: Vector = Vector::new(0.0, 3.0, 0.0);
+
+/// Characters that fall below this height respawn.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct KillPlane {
+    pub height: Scalar,
+}
+
+impl Default for KillPlane {
+    fn default() -> Self {
+        Self { height: -30.0 }
+    }
+}
+
+/// A sensor volume that respawns characters touching it.
+#[derive(Component, Clone, Copy, Debug, Default)]
+#[require(Sensor)]
+pub struct OutOfBounds;
+
+/// Why a character respawned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RespawnCause {
+    /// It fell below the [`KillPlane`].
+    KillPlane,
+    /// It touched this [`OutOfBounds`] volume.
+    OutOfBounds(Entity),
+    /// A level was entered.
+    LevelEntered,
+    /// Some other plugin asked for it.
+    Requested,
+}
+
+/// Send to respawn a character controller at a [`SpawnPoint`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct RespawnRequested {
+    pub entity: Entity,
+    pub cause: RespawnCause,
+}
+
+/// Sent after a character controller respawned.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Respawned {
+    pub entity: Entity,
+    pub cause: RespawnCause,
+    /// The spawn point it respawned at, if there was one.
+    pub spawn_point: Option<Entity>,
+    /// Where it was before respawning.
+    pub from: Vector,
+    pub to: Vector,
+}
+
+/// Requests a respawn for character controllers that left the world.
+fn detect_out_of_world(
+    kill_plane: Res<KillPlane>,
+    spatial_query: SpatialQuery,
+    volumes: Query<(), With<OutOfBounds>>,
+    characters: Query<(Entity, &Collider, &Position, &Rotation), With<CharacterController>>,
+    mut requests: EventWriter<RespawnRequested>,
+) {
+    for (entity, collider, position, rotation) in &characters {
+        let cause = if position.y < kill_plane.height {
+            Some(RespawnCause::KillPlane)
+        } else {
+            spatial_query
+                .shape_intersections(
+                    collider,
+                    position.0,
+                    rotation.0,
+                    &SpatialQueryFilter::from_excluded_entities([entity]),
+                )
+                .into_iter()
+                .find(|volume| volumes.contains(*volume))
+                .map(RespawnCause::OutOfBounds)
+        };
+        if let Some(cause) = cause {
+            requests.write(RespawnRequested { entity, cause });
+        }
+    }
+}
+
+/// Puts requested characters at a safe spot at the [`SpawnPoint`] with the lowest index, stopped,
+/// standing and rested, and snaps the [`MainCamera`] back to the [`MainCharacter`] if the player
+/// controls it, looking the way the spawn point does.
+pub(super) fn respawn_characters(
+    mut commands: Commands,
+    mut requests: EventReader<RespawnRequested>,
+    mut respawned: EventWriter<Respawned>,
+    spatial_query: SpatialQuery,
+    sensors: Query<(), With<Sensor>>,
+    spawn_points: Query<(Entity, &SpawnPoint, &Transform)>,
+    mut characters: Query<
+        (
+            &mut Collider,
+            &mut Position,
+            &mut Rotation,
+            &mut LinearVelocity,
+            &mut PlatformVelocity,
+            &mut UpDirection,
+            &mut MovementInput,
+            Option<(&Stances, &mut Stance, &mut ShapeCaster)>,
+            Option<&mut Sprint>,
+            Option<&EyeOffset>,
+            Has<MainCharacter>,
+        ),
+        With<CharacterController>,
+    >,
+    mut main_camera: Query<(Entity, &mut Transform), With<MainCamera>>,
+    camera_state: Res<State<CameraState>>,
+) {
+    // Query order changes as entities come and go, the index doesn't.
+    let spawn_point = spawn_points
+        .iter()
+        .min_by_key(|(_, spawn_point, _)| spawn_point.index);
+    let (spawn_position, spawn_rotation, spawn_view) = spawn_point.map_or(
+        (FALLBACK_SPAWN_POSITION, Quat::IDENTITY, Quat::IDENTITY),
+        |(_, spawn_point, transform)| (transform.translation, transform.rotation, spawn_point.view),
+    );
+
+    let mut handled = HashSet::new();
+    for request in requests.read() {
+        if !handled.insert(request.entity) {
+            continue;
+        }
+        let Ok((
+            mut collider,
+            mut position,
+            mut rotation,
+            mut linear_velocity,
+            mut platform_velocity,
+            mut up,
+            mut input,
+            stance,
+            sprint,
+            eye_offset,
+            is_main_character,
+        )) = characters.get_mut(request.entity)
+        else {
+            continue;
+        };
+
+        // Back on its feet, so the spot is found for the standing collider.
+        if let Some((stances, mut stance, mut caster)) = stance {
+            stances.set_stance(Stance::Stand, &mut stance, &mut collider, &mut caster);
+        }
+        input.prone = false;
+        if let Some(mut sprint) = sprint {
+            sprint.rest();
+        }
+
+        let from = position.0;
+        *up = UpDirection::default();
+        rotation.0 = spawn_rotation;
+        // The colliders of a level that was just entered aren't in the spatial query yet.
+        position.0 = if request.cause == RespawnCause::LevelEntered {
+            spawn_position
+        } else {
+            find_safe_position(
+                &spatial_query,
+                &sensors,
+                &collider,
+                spawn_rotation,
+                spawn_position,
+                up.0,
+                &SpatialQueryFilter::from_excluded_entities([request.entity]),
+            )
+            .unwrap_or_else(|| {
+                warn!("No free spot near the spawn point at {spawn_position}, spawning inside");
+                spawn_position
+            })
+        };
+        linear_velocity.0 = Vector::ZERO;
+        platform_velocity.0 = Vector::ZERO;
+        commands
+            .entity(request.entity)
+            .remove::<(Grounded, GroundedOn, TraversalMode, Submerged, Swimming)>()
+            .insert(JumpState::default());
+
+        if is_main_character && camera_state.controls_character() {
+            for (camera, mut camera_transform) in &mut main_camera {
+                let eyes = Transform::from_translation(position.0).with_rotation(spawn_rotation);
+                camera_transform.translation =
+                    eye_offset.copied().unwrap_or_default().eye_position(&eyes);
+                camera_transform.rotation = spawn_view;
+                commands.entity(camera).remove::<CameraTransition>();
+            }
+        }
+
+        respawned.write(Respawned {
+            entity: request.entity,
+            cause: request.cause,
+            spawn_point: spawn_point.map(|(entity, _, _)| entity),
+            from,
+            to: position.0,
+        });
+    }
+}
+
+fn log_respawns(mut respawned: EventReader<Respawned>) {
+    for respawn in respawned.read() {
+        info!(
+            "Respawned {} ({:?}) at {}",
+            respawn.entity, respawn.cause, respawn.to
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+    use super::*;
+    use crate::character_controller::{
+        CharacterControllerBundle, CharacterControllerPlugin, MovementBundle,
+    };
+    use crate::input::ActionState;
+
+    /// The spawn point with the lowest index, which isn't the first one spawned.
+    const SPAWN_POSITION: Vector = Vector::new(-5.0, 2.0, 0.0);
+
+    /// An app with ground, two spawn points and a prone character on the ground that used up its
+    /// stamina.
+    fn app() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            StatesPlugin,
+            PhysicsPlugins::default(),
+            CharacterControllerPlugin,
+            plugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_state::<CameraState>()
+        .init_resource::<ActionState>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )));
+        app.world_mut().spawn((MainCamera, Transform::default()));
+        app.world_mut().spawn((
+            RigidBody::Static,
+            Collider::cuboid(40.0, 1.0, 40.0),
+            Position(Vector::NEG_Y * 0.5),
+            Transform::from_xyz(0.0, -0.5, 0.0),
+        ));
+        for (index, position) in [(1, Vector::new(10.0, 2.0, 0.0)), (0, SPAWN_POSITION)] {
+            app.world_mut().spawn((
+                SpawnPoint {
+                    index,
+                    view: Quat::IDENTITY,
+                },
+                Transform::from_translation(position.f32()),
+            ));
+        }
+
+        let character = app
+            .world_mut()
+            .spawn((
+                CharacterControllerBundle::new(Collider::capsule(0.4, 1.0), Vector::NEG_Y * 9.81)
+                    .with_movement_bundle(MovementBundle::default()),
+                Stances::capsule(0.4, 1.0),
+                Sprint::default(),
+                Position(Vector::Y * 0.92),
+                Transform::from_xyz(0.0, 0.92, 0.0),
+            ))
+            .id();
+        app.world_mut()
+            .get_mut::<MovementInput>(character)
+            .unwrap()
+            .prone = true;
+        for _ in 0..20 {
+            app.update();
+        }
+        assert_eq!(app.world().get::<Stance>(character), Some(&Stance::Prone));
+        app.world_mut()
+            .get_mut::<Sprint>(character)
+            .unwrap()
+            .stamina = 0.0;
+        (app, character)
+    }
+
+    /// Moves `character` to `position` and checks that it respawns for `cause` in the next update.
+    fn assert_respawns(app: &mut App, character: Entity, position: Vector, cause: RespawnCause) {
+        app.world_mut().entity_mut(character).insert((
+            Position(position),
+            LinearVelocity(Vector::new(3.0, -20.0, 0.0)),
+        ));
+        app.update();
+
+        let requests = app.world().resource::<Events<RespawnRequested>>();
+        assert!(
+            requests
+                .get_cursor()
+                .read(requests)
+                .any(|request| request.entity == character && request.cause == cause)
+        );
+        let respawned = app.world().resource::<Events<Respawned>>();
+        let respawn = respawned
+            .get_cursor()
+            .read(respawned)
+            .last()
+            .copied()
+            .unwrap();
+        assert_eq!(respawn.cause, cause);
+
+        let world = app.world();
+        let spawn_point = world
+            .get::<SpawnPoint>(respawn.spawn_point.unwrap())
+            .unwrap();
+        assert_eq!(spawn_point.index, 0);
+        let respawned_at = world.get::<Position>(character).unwrap().0;
+        assert!(
+            respawned_at.distance(SPAWN_POSITION) < 1.0,
+            "{respawned_at}"
+        );
+        assert_eq!(
+            world.get::<LinearVelocity>(character).unwrap().0,
+            Vector::ZERO
+        );
+
+        // Standing and rested.
+        assert_eq!(world.get::<Stance>(character), Some(&Stance::Stand));
+        assert!(!world.get::<MovementInput>(character).unwrap().prone);
+        let sprint = world.get::<Sprint>(character).unwrap();
+        assert!(!sprint.is_sprinting());
+        assert_eq!(sprint.stamina, sprint.max_stamina);
+    }
+
+    #[test]
+    fn falling_below_the_kill_plane_respawns() {
+        let (mut app, character) = app();
+        assert_respawns(
+            &mut app,
+            character,
+            Vector::new(0.0, -40.0, 0.0),
+            RespawnCause::KillPlane,
+        );
+    }
+
+    #[test]
+    fn touching_out_of_bounds_volumes_respawns() {
+        let (mut app, character) = app();
+        let volume = app
+            .world_mut()
+            .spawn((
+                OutOfBounds,
+                Collider::cuboid(4.0, 4.0, 4.0),
+                Position(Vector::new(15.0, 2.0, 15.0)),
+                Transform::from_xyz(15.0, 2.0, 15.0),
+            ))
+            .id();
+        // Let the volume reach the spatial query first.
+        app.update();
+        assert_respawns(
+            &mut app,
+            character,
+            Vector::new(15.0, 2.0, 15.0),
+            RespawnCause::OutOfBounds(volume),
+        );
+    }
+}
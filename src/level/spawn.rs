@@ -0,0 +1,162 @@
+//! Spawning the entities of a [`Level`].
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use super::{
+    Block, LevelEntity, SpawnPoint,
+    format::{BehaviorDefinition, Level, LightDefinition, ObjectDefinition},
+    respawn::OutOfBounds,
+};
+use crate::camera::framing::CameraTarget;
+use crate::character_controller::{FluidVolume, GravityField, Ladder, Ledge};
+use crate::spray::{
+    canvas::PaintCanvas,
+    coverage::{CoverageThreshold, CoverageThresholds},
+};
+
+/// Spawns everything in `level`, each entity marked as a [`LevelEntity`].
+pub fn spawn_level(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    level: &Level,
+) {
+    for (index, spawn_point) in level.spawn_points.iter().enumerate() {
+        let mut transform = Transform::from_translation(spawn_point.position);
+        let mut view = Quat::IDENTITY;
+        if let Some(target) = spawn_point.look_at {
+            // The character faces the target but stays upright, the camera looks right at it.
+            transform.look_at(target.with_y(spawn_point.position.y), Vec3::Y);
+            view = transform.looking_at(target, Vec3::Y).rotation;
+        }
+        commands.spawn((
+            Name::new("Spawn point"),
+            SpawnPoint { index, view },
+            transform,
+            LevelEntity,
+        ));
+    }
+
+    for light in &level.lights {
+        match *light {
+            LightDefinition::Point {
+                position,
+                intensity,
+                range,
+                shadows,
+                color,
+            } => commands.spawn((
+                PointLight {
+                    intensity,
+                    range,
+                    shadows_enabled: shadows,
+                    color: Color::srgba_u8(color[0], color[1], color[2], color[3]),
+                    ..default()
+                },
+                Transform::from_translation(position),
+                LevelEntity,
+            )),
+            LightDefinition::Directional {
+                direction,
+                illuminance,
+                shadows,
+                color,
+            } => commands.spawn((
+                DirectionalLight {
+                    illuminance,
+                    shadows_enabled: shadows,
+                    color: Color::srgba_u8(color[0], color[1], color[2], color[3]),
+                    ..default()
+                },
+                Transform::default().looking_to(direction, Vec3::Y),
+                LevelEntity,
+            )),
+        };
+    }
+
+    for object in &level.objects {
+        spawn_object(commands, meshes, materials, object);
+    }
+}
+
+fn spawn_object(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    object: &ObjectDefinition,
+) {
+    let mut entity = commands.spawn((
+        Transform::from_translation(object.position).with_rotation(object.rotation),
+        LevelEntity,
+    ));
+    if let Some(name) = &object.name {
+        entity.insert(Name::new(name.clone()));
+    }
+
+    if let Some(mesh) = object.mesh {
+        let [red, green, blue, alpha] = object.material.color;
+        entity.insert((
+            Mesh3d(meshes.add(mesh.mesh())),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgba_u8(red, green, blue, alpha),
+                alpha_mode: if object.material.transparent {
+                    AlphaMode::Blend
+                } else {
+                    AlphaMode::Opaque
+                },
+                ..default()
+            })),
+        ));
+
+        if let Some(resolution) = object.paintable {
+            match mesh.paint_layout() {
+                Some(layout) => {
+                    entity.insert(PaintCanvas::new(layout, resolution));
+                }
+                None => warn!("{mesh:?} meshes can't be painted on"),
+            }
+        }
+        if !object.coverage_goals.is_empty() {
+            entity.insert(CoverageThresholds(
+                object
+                    .coverage_goals
+                    .iter()
+                    .map(|goal| {
+                        let threshold = CoverageThreshold::new(goal.fraction);
+                        match &goal.faces {
+                            Some(faces) => threshold.on_faces(faces.iter().copied()),
+                            None => threshold,
+                        }
+                    })
+                    .collect(),
+            ));
+        }
+    }
+
+    if let Some(collider) = object.collider {
+        let body = RigidBody::from(object.body);
+        entity.insert((collider.collider(), body));
+        if body.is_dynamic() || body.is_kinematic() {
+            entity.insert(TransformInterpolation);
+        }
+        if object.angular_velocity != Vec3::ZERO {
+            entity.insert(AngularVelocity(object.angular_velocity));
+        }
+    }
+
+    for behavior in &object.behaviors {
+        match *behavior {
+            BehaviorDefinition::Block => entity.insert(Block),
+            BehaviorDefinition::CameraTarget => entity.insert(CameraTarget),
+            BehaviorDefinition::Ledge => entity.insert(Ledge),
+            BehaviorDefinition::Ladder => entity.insert(Ladder::default()),
+            BehaviorDefinition::Water => entity.insert(FluidVolume::water()),
+            BehaviorDefinition::Mud => entity.insert(FluidVolume::mud()),
+            BehaviorDefinition::OutOfBounds => entity.insert(OutOfBounds),
+            BehaviorDefinition::SphericalGravity { strength } => {
+                entity.insert(GravityField::spherical(strength))
+            }
+        };
+    }
+}
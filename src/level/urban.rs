@@ -0,0 +1,597 @@
+//! A generated city to paint: a grid of streets lined with buildings, with alleyways, fenced
+//! lots and underpasses.
+//!
+//! The generator only draws from the random number generator it is given, in a fixed order, so a
+//! seed always gives the same [`Level`]. Everything is a cuboid with a collider. Buildings, fences
+//! and underpasses can be painted on, with a canvas resolution that follows their size, until the
+//! texel budget of the city is used up. The street and sidewalks only get a canvas when
+//! [`UrbanSettings::paintable_ground`] asks for it.
+
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use super::format::{
+    BehaviorDefinition, Level, LightDefinition, MaterialDefinition, ObjectDefinition,
+    ShapeDefinition, SpawnPointDefinition,
+};
+use crate::bevy_starter::prelude::{GameRng, RngStream, random_number};
+
+/// The street surface, and where everything else stands on.
+const ASPHALT: [u8; 4] = [62, 62, 68, 255];
+const SIDEWALK: [u8; 4] = [150, 150, 145, 255];
+const CONCRETE: [u8; 4] = [175, 170, 160, 255];
+const FENCE: [u8; 4] = [110, 115, 105, 255];
+/// Building facades are picked from these.
+const FACADES: &[[u8; 4]] = &[
+    [210, 180, 160, 255],
+    [190, 120, 100, 255],
+    [230, 220, 200, 255],
+    [160, 170, 185, 255],
+    [200, 190, 140, 255],
+    [140, 150, 130, 255],
+];
+
+const GROUND_THICKNESS: f32 = 0.5;
+const SIDEWALK_HEIGHT: f32 = 0.15;
+const FENCE_HEIGHT: f32 = 1.6;
+const FENCE_THICKNESS: f32 = 0.1;
+/// The opening left in the street side of a fenced lot.
+const GATE_WIDTH: f32 = 2.0;
+const UNDERPASS_CLEARANCE: f32 = 4.5;
+const UNDERPASS_DECK_THICKNESS: f32 = 0.6;
+const UNDERPASS_WALL_THICKNESS: f32 = 0.6;
+/// The shortest a lot gets, so alleyways never take up a whole block.
+const MIN_LOT_LENGTH: f32 = 4.0;
+/// The smallest any size from the settings is taken to be.
+const MIN_SIZE: f32 = 0.1;
+/// The largest face cell of a canvas in texels, which keeps canvases within
+/// [`MAX_CANVAS_TEXELS`](crate::spray::canvas::MAX_CANVAS_TEXELS).
+const MAX_CELL: f32 = 384.0;
+/// The street and sidewalks are large and flat, so they get fewer texels per unit and smaller
+/// cells.
+const GROUND_TEXEL_SCALE: f32 = 0.25;
+const MAX_GROUND_CELL: f32 = 128.0;
+
+/// What the generated city looks like. Change it to generate another city.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct UrbanSettings {
+    pub seed: u64,
+    /// How many blocks there are along X and Z.
+    pub blocks: UVec2,
+    /// The side of a square block, sidewalk included.
+    pub block_size: f32,
+    pub street_width: f32,
+    pub alley_width: f32,
+    /// The most lots a block is split into, with an alleyway between each two.
+    pub max_lots: u32,
+    pub min_building_height: f32,
+    pub max_building_height: f32,
+    /// How likely a lot is left empty and fenced instead of built on.
+    pub fenced_lot_chance: f32,
+    /// How likely an alleyway is closed off by a fence.
+    pub alley_fence_chance: f32,
+    /// How likely a street between two blocks is crossed by an underpass.
+    pub underpass_chance: f32,
+    /// The canvas resolution of paintable surfaces, per unit of their largest side.
+    pub texels_per_unit: f32,
+    /// Whether the street and sidewalks can be painted on too.
+    pub paintable_ground: bool,
+    /// The most canvas texels the whole city may have. Surfaces beyond it can't be painted on.
+    pub max_canvas_texels: u32,
+}
+
+impl Default for UrbanSettings {
+    fn default() -> Self {
+        Self {
+            seed: 0x5EED,
+            blocks: UVec2::new(3, 3),
+            block_size: 24.0,
+            street_width: 8.0,
+            alley_width: 2.5,
+            max_lots: 3,
+            min_building_height: 5.0,
+            max_building_height: 18.0,
+            fenced_lot_chance: 0.2,
+            alley_fence_chance: 0.3,
+            underpass_chance: 0.3,
+            texels_per_unit: 8.0,
+            paintable_ground: false,
+            max_canvas_texels: 8 << 20,
+        }
+    }
+}
+
+impl UrbanSettings {
+    /// The default settings, with a seed drawn from the [`RngStream::LevelGeneration`] stream.
+    pub fn from_rng(rng: &mut GameRng) -> Self {
+        Self {
+            seed: rng.stream(RngStream::LevelGeneration).random(),
+            ..default()
+        }
+    }
+
+    /// Generates the city for [`UrbanSettings::seed`].
+    pub fn generate(&self) -> Level {
+        let mut rng = GameRng::new(self.seed);
+        generate_urban_level(self, rng.stream(RngStream::LevelGeneration))
+    }
+
+    /// The size of the whole city along X and Z, with a street all around.
+    pub fn extent(&self) -> Vec2 {
+        self.blocks.as_vec2() * (self.block_size + self.street_width) + self.street_width
+    }
+
+    /// These settings with every size finite and positive and every chance between 0 and 1, so
+    /// no settings give shapes that are inverted, empty or NaN.
+    fn sanitized(&self) -> Self {
+        let size = |value: f32| {
+            if value.is_finite() {
+                value.max(MIN_SIZE)
+            } else {
+                MIN_SIZE
+            }
+        };
+        let chance = |value: f32| {
+            if value.is_nan() {
+                0.0
+            } else {
+                value.clamp(0.0, 1.0)
+            }
+        };
+        let min_building_height = size(self.min_building_height);
+        Self {
+            seed: self.seed,
+            blocks: self.blocks,
+            block_size: size(self.block_size).max(MIN_LOT_LENGTH),
+            street_width: size(self.street_width),
+            alley_width: size(self.alley_width),
+            max_lots: self.max_lots.max(1),
+            min_building_height,
+            max_building_height: size(self.max_building_height).max(min_building_height),
+            fenced_lot_chance: chance(self.fenced_lot_chance),
+            alley_fence_chance: chance(self.alley_fence_chance),
+            underpass_chance: chance(self.underpass_chance),
+            texels_per_unit: size(self.texels_per_unit),
+            paintable_ground: self.paintable_ground,
+            max_canvas_texels: self.max_canvas_texels,
+        }
+    }
+
+    /// The center of the street before block `index` along one axis, or after the last block.
+    fn street_center(&self, extent: f32, index: u32) -> f32 {
+        -extent / 2.0
+            + self.street_width / 2.0
+            + index as f32 * (self.block_size + self.street_width)
+    }
+}
+
+/// Generates a city as laid out by `settings`, drawing from `rng`.
+pub fn generate_urban_level(settings: &UrbanSettings, rng: &mut impl Rng) -> Level {
+    let settings = &settings.sanitized();
+    let mut city = CityBuilder {
+        settings,
+        objects: Vec::new(),
+        canvas_texels: 0,
+        unpainted: 0,
+    };
+    let extent = settings.extent();
+
+    city.ground_cuboid(
+        "Street",
+        Vec3::new(0.0, -GROUND_THICKNESS / 2.0, 0.0),
+        Vec3::new(extent.x, GROUND_THICKNESS, extent.y),
+        ASPHALT,
+    );
+
+    for z in 0..settings.blocks.y {
+        for x in 0..settings.blocks.x {
+            let min = Vec2::new(
+                settings.street_center(extent.x, x) + settings.street_width / 2.0,
+                settings.street_center(extent.y, z) + settings.street_width / 2.0,
+            );
+            city.block(rng, min);
+        }
+    }
+
+    // Underpasses cross the streets between blocks, along X and then along Z.
+    for z in 0..settings.blocks.y {
+        for x in 1..settings.blocks.x {
+            if rng.random_bool(settings.underpass_chance as f64) {
+                let block_z = settings.street_center(extent.y, z) + settings.street_width / 2.0;
+                let along = random_number(rng, 0.25, 0.75) * settings.block_size + block_z;
+                let center = Vec3::new(settings.street_center(extent.x, x), 0.0, along);
+                city.underpass(center, Quat::IDENTITY);
+            }
+        }
+    }
+    for x in 0..settings.blocks.x {
+        for z in 1..settings.blocks.y {
+            if rng.random_bool(settings.underpass_chance as f64) {
+                let block_x = settings.street_center(extent.x, x) + settings.street_width / 2.0;
+                let along = random_number(rng, 0.25, 0.75) * settings.block_size + block_x;
+                let center = Vec3::new(along, 0.0, settings.street_center(extent.y, z));
+                city.underpass(center, Quat::from_rotation_y(FRAC_PI_2));
+            }
+        }
+    }
+
+    if city.unpainted > 0 {
+        warn!(
+            "{} surfaces of the city can't be painted on, its canvas texel budget is used up",
+            city.unpainted
+        );
+    }
+
+    // Start at the crossing closest to the center, looking down a street.
+    let start = Vec3::new(
+        settings.street_center(extent.x, settings.blocks.x / 2),
+        2.0,
+        settings.street_center(extent.y, settings.blocks.y / 2),
+    );
+    Level {
+        name: format!("City {:#x}", settings.seed),
+        spawn_points: vec![SpawnPointDefinition {
+            position: start,
+            look_at: Some(start + Vec3::NEG_Z * settings.block_size),
+        }],
+        kill_height: Some(-20.0),
+        lights: vec![LightDefinition::Directional {
+            direction: Vec3::new(-0.4, -1.0, -0.3),
+            illuminance: 8000.0,
+            shadows: true,
+            color: [255; 4],
+        }],
+        objects: city.objects,
+    }
+}
+
+/// Collects the objects of a city while it is generated.
+struct CityBuilder<'a> {
+    settings: &'a UrbanSettings,
+    objects: Vec<ObjectDefinition>,
+    /// The canvas texels given out so far.
+    canvas_texels: u64,
+    /// How many surfaces didn't get a canvas, as the budget was used up.
+    unpainted: usize,
+}
+
+impl CityBuilder<'_> {
+    /// A block with its lower X and Z corner at `min`: a sidewalk split into lots, each either
+    /// built on or fenced, with alleyways in between.
+    fn block(&mut self, rng: &mut impl Rng, min: Vec2) {
+        let settings = self.settings;
+        let size = settings.block_size;
+        self.ground_cuboid(
+            "Sidewalk",
+            Vec3::new(
+                min.x + size / 2.0,
+                SIDEWALK_HEIGHT / 2.0,
+                min.y + size / 2.0,
+            ),
+            Vec3::new(size, SIDEWALK_HEIGHT, size),
+            SIDEWALK,
+        );
+
+        // Lots are laid out side by side along one axis, and span the block along the other.
+        let along_x = rng.random_bool(0.5);
+        // Only as many lots as fit at their shortest with the alleyways between them.
+        let fitting = (size + settings.alley_width) / (MIN_LOT_LENGTH + settings.alley_width);
+        let lots = rng.random_range(1..=settings.max_lots.min(fitting as u32).max(1));
+        let usable = size - (lots - 1) as f32 * settings.alley_width;
+        let weights: Vec<f32> = (0..lots).map(|_| random_number(rng, 1.0, 2.0)).collect();
+        let total_weight: f32 = weights.iter().sum();
+
+        let mut start = 0.0;
+        for (lot, weight) in weights.iter().enumerate() {
+            let length = usable * weight / total_weight;
+            // Lots are (along, across) in block space, turned into X and Z here.
+            let to_world = |along: f32, across: f32| {
+                if along_x {
+                    Vec2::new(min.x + along, min.y + across)
+                } else {
+                    Vec2::new(min.x + across, min.y + along)
+                }
+            };
+            let lot_min = to_world(start, 0.0);
+            let lot_max = to_world(start + length, size);
+            let (lot_min, lot_max) = (lot_min.min(lot_max), lot_min.max(lot_max));
+
+            if rng.random_bool(settings.fenced_lot_chance as f64) {
+                self.fenced_lot(lot_min, lot_max);
+            } else {
+                self.building(rng, lot_min, lot_max);
+            }
+
+            let alley_start = start + length;
+            start = alley_start + settings.alley_width;
+            if lot + 1 < weights.len() && rng.random_bool(settings.alley_fence_chance as f64) {
+                // Close the alleyway off halfway through.
+                let center = to_world(alley_start + settings.alley_width / 2.0, size / 2.0);
+                let fence_size = if along_x {
+                    Vec3::new(settings.alley_width, FENCE_HEIGHT, FENCE_THICKNESS)
+                } else {
+                    Vec3::new(FENCE_THICKNESS, FENCE_HEIGHT, settings.alley_width)
+                };
+                self.fence(Vec3::new(center.x, 0.0, center.y), fence_size);
+            }
+        }
+    }
+
+    /// A building set back a little from the edges of its lot.
+    fn building(&mut self, rng: &mut impl Rng, lot_min: Vec2, lot_max: Vec2) {
+        let settings = self.settings;
+        let lot_size = lot_max - lot_min;
+        let setback = random_number(rng, 0.5, 1.5).min(lot_size.min_element() / 4.0);
+        let footprint = lot_size - 2.0 * setback;
+        let height = if settings.min_building_height < settings.max_building_height {
+            random_number(
+                rng,
+                settings.min_building_height,
+                settings.max_building_height,
+            )
+        } else {
+            settings.min_building_height
+        };
+        let color = FACADES[rng.random_range(0..FACADES.len())];
+
+        let center = (lot_min + lot_max) / 2.0;
+        let index = self.objects.len();
+        let mut building = self.cuboid(
+            &format!("Building {index}"),
+            Vec3::new(center.x, SIDEWALK_HEIGHT + height / 2.0, center.y),
+            Vec3::new(footprint.x, height, footprint.y),
+            color,
+        );
+        self.make_paintable(&mut building, self.settings.texels_per_unit, MAX_CELL);
+        building.behaviors.push(BehaviorDefinition::Ledge);
+        self.objects.push(building);
+    }
+
+    /// An empty lot with a fence along its edges, and a gate in the middle of each side.
+    fn fenced_lot(&mut self, lot_min: Vec2, lot_max: Vec2) {
+        let inset = 0.5;
+        let (min, max) = (lot_min + inset, lot_max - inset);
+        for z in [min.y, max.y] {
+            self.gated_fence(Vec3::new(min.x, 0.0, z), Vec3::new(max.x, 0.0, z));
+        }
+        for x in [min.x, max.x] {
+            self.gated_fence(Vec3::new(x, 0.0, min.y), Vec3::new(x, 0.0, max.y));
+        }
+    }
+
+    /// A straight fence from `start` to `end`, with a gate in the middle if it is long enough.
+    fn gated_fence(&mut self, start: Vec3, end: Vec3) {
+        let length = start.distance(end);
+        let Some(direction) = (end - start).try_normalize() else {
+            return;
+        };
+        let thickness = Vec3::splat(FENCE_THICKNESS) * (Vec3::ONE - direction.abs());
+        let size = |length: f32| direction.abs() * length + thickness.with_y(FENCE_HEIGHT);
+
+        if length > 2.0 * GATE_WIDTH {
+            let part = (length - GATE_WIDTH) / 2.0;
+            self.fence(start + direction * part / 2.0, size(part));
+            self.fence(end - direction * part / 2.0, size(part));
+        } else {
+            self.fence((start + end) / 2.0, size(length));
+        }
+    }
+
+    /// A fence standing on the sidewalk, at `center` on the ground.
+    fn fence(&mut self, center: Vec3, size: Vec3) {
+        let center = center.with_y(SIDEWALK_HEIGHT + FENCE_HEIGHT / 2.0);
+        self.paintable_cuboid("Fence", center, size, FENCE);
+    }
+
+    /// A deck across a street at `center`, standing on a wall at each side of the street. The
+    /// street runs along Z before `rotation`.
+    fn underpass(&mut self, center: Vec3, rotation: Quat) {
+        let settings = self.settings;
+        let width = 5.0;
+        let wall_offset = settings.street_width / 2.0 - UNDERPASS_WALL_THICKNESS / 2.0;
+        for side in [-1.0, 1.0] {
+            let position = center + rotation * Vec3::new(side * wall_offset, 0.0, 0.0);
+            let mut wall = self.cuboid(
+                "Underpass wall",
+                position.with_y(UNDERPASS_CLEARANCE / 2.0),
+                Vec3::new(UNDERPASS_WALL_THICKNESS, UNDERPASS_CLEARANCE, width),
+                CONCRETE,
+            );
+            wall.rotation = rotation;
+            self.make_paintable(&mut wall, settings.texels_per_unit, MAX_CELL);
+            self.objects.push(wall);
+        }
+
+        let mut deck = self.cuboid(
+            "Underpass",
+            center.with_y(UNDERPASS_CLEARANCE + UNDERPASS_DECK_THICKNESS / 2.0),
+            Vec3::new(settings.street_width, UNDERPASS_DECK_THICKNESS, width),
+            CONCRETE,
+        );
+        deck.rotation = rotation;
+        self.make_paintable(&mut deck, settings.texels_per_unit, MAX_CELL);
+        deck.behaviors.push(BehaviorDefinition::Ledge);
+        self.objects.push(deck);
+    }
+
+    /// A static cuboid that can be seen, collided with and painted on.
+    fn paintable_cuboid(&mut self, name: &str, center: Vec3, size: Vec3, color: [u8; 4]) {
+        let mut object = self.cuboid(name, center, size, color);
+        self.make_paintable(&mut object, self.settings.texels_per_unit, MAX_CELL);
+        self.objects.push(object);
+    }
+
+    /// A piece of the street or sidewalk, only painted on if the settings ask for it.
+    fn ground_cuboid(&mut self, name: &str, center: Vec3, size: Vec3, color: [u8; 4]) {
+        let mut object = self.cuboid(name, center, size, color);
+        if self.settings.paintable_ground {
+            let texels_per_unit = self.settings.texels_per_unit * GROUND_TEXEL_SCALE;
+            self.make_paintable(&mut object, texels_per_unit, MAX_GROUND_CELL);
+        }
+        self.objects.push(object);
+    }
+
+    /// Gives `object` a canvas with cells of at most `max_cell` texels, if it fits in what is left
+    /// of the texel budget.
+    fn make_paintable(
+        &mut self,
+        object: &mut ObjectDefinition,
+        texels_per_unit: f32,
+        max_cell: f32,
+    ) {
+        let resolution = resolution(object.mesh, texels_per_unit, max_cell);
+        let texels = resolution.x as u64 * resolution.y as u64;
+        if self.canvas_texels + texels > self.settings.max_canvas_texels as u64 {
+            self.unpainted += 1;
+            return;
+        }
+        self.canvas_texels += texels;
+        object.paintable = Some(resolution);
+    }
+
+    /// A static cuboid that can be seen and collided with.
+    fn cuboid(&self, name: &str, center: Vec3, size: Vec3, color: [u8; 4]) -> ObjectDefinition {
+        let shape = ShapeDefinition::Cuboid { size };
+        ObjectDefinition {
+            name: Some(name.to_string()),
+            position: center,
+            rotation: Quat::IDENTITY,
+            mesh: Some(shape),
+            material: MaterialDefinition {
+                color,
+                transparent: false,
+            },
+            collider: Some(shape),
+            body: default(),
+            angular_velocity: Vec3::ZERO,
+            paintable: None,
+            coverage_goals: Vec::new(),
+            behaviors: Vec::new(),
+        }
+    }
+}
+
+/// The canvas resolution for a cuboid, whose faces are laid out in a 3x2 grid of cells.
+fn resolution(shape: Option<ShapeDefinition>, texels_per_unit: f32, max_cell: f32) -> UVec2 {
+    let Some(ShapeDefinition::Cuboid { size }) = shape else {
+        return UVec2::splat(256);
+    };
+    let cell = (size.max_element() * texels_per_unit).clamp(32.0, max_cell);
+    // Round up to a multiple of 32 texels.
+    let cell = (cell / 32.0).ceil() as u32 * 32;
+    UVec2::new(3 * cell, 2 * cell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spray::canvas::MAX_CANVAS_TEXELS;
+
+    /// The bounds of every object of `level`, in order.
+    fn bounds(level: &Level) -> Vec<(Vec3A, Vec3A)> {
+        level
+            .objects
+            .iter()
+            .map(|object| {
+                let aabb = object.aabb().expect("generated objects have a shape");
+                (aabb.min, aabb.max)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn seeds_always_generate_the_same_city() {
+        let settings = UrbanSettings::default();
+        let city = settings.generate();
+        let again = settings.generate();
+        assert_eq!(city.objects.len(), again.objects.len());
+        assert_eq!(bounds(&city), bounds(&again));
+
+        let other = UrbanSettings {
+            seed: settings.seed + 1,
+            ..settings
+        }
+        .generate();
+        assert_ne!(bounds(&city), bounds(&other));
+    }
+
+    #[test]
+    fn canvases_stay_within_budget() {
+        let canvases = |settings: &UrbanSettings| -> Vec<(Option<String>, UVec2)> {
+            settings
+                .generate()
+                .objects
+                .into_iter()
+                .filter_map(|object| Some((object.name, object.paintable?)))
+                .collect()
+        };
+        let texels = |canvases: &[(Option<String>, UVec2)]| -> u64 {
+            canvases
+                .iter()
+                .map(|(_, size)| size.x as u64 * size.y as u64)
+                .sum()
+        };
+
+        let settings = UrbanSettings::default();
+        let city = canvases(&settings);
+        assert!(!city.is_empty());
+        assert!(texels(&city) <= settings.max_canvas_texels as u64);
+        for (name, size) in &city {
+            assert!(size.x * size.y <= MAX_CANVAS_TEXELS, "{name:?} is {size}");
+            // The ground can't be painted on unless asked for.
+            assert!(
+                !matches!(name.as_deref(), Some("Street" | "Sidewalk")),
+                "{name:?}"
+            );
+        }
+
+        // A small budget leaves the rest of the surfaces bare.
+        let tight = UrbanSettings {
+            paintable_ground: true,
+            max_canvas_texels: 1 << 20,
+            ..settings
+        };
+        let city = canvases(&tight);
+        assert_eq!(city[0].0.as_deref(), Some("Street"));
+        assert!(texels(&city) <= 1 << 20);
+    }
+
+    #[test]
+    fn degenerate_settings_give_positive_sizes() {
+        let wide_alleys = UrbanSettings {
+            alley_width: 30.0,
+            max_lots: 10,
+            fenced_lot_chance: 0.5,
+            alley_fence_chance: 1.0,
+            ..default()
+        };
+        let broken = UrbanSettings {
+            block_size: 0.0,
+            street_width: -1.0,
+            alley_width: f32::NAN,
+            min_building_height: 10.0,
+            max_building_height: -5.0,
+            fenced_lot_chance: 1.0,
+            alley_fence_chance: f32::NAN,
+            underpass_chance: f32::INFINITY,
+            texels_per_unit: f32::NAN,
+            ..default()
+        };
+
+        for settings in [wide_alleys, broken] {
+            for object in settings.generate().objects {
+                let Some(ShapeDefinition::Cuboid { size }) = object.collider else {
+                    panic!("{:?} isn't a cuboid", object.name);
+                };
+                assert!(
+                    size.is_finite() && size.min_element() > 0.0,
+                    "{:?} has size {size}",
+                    object.name
+                );
+                assert!(object.position.is_finite(), "{:?}", object.name);
+            }
+        }
+    }
+}
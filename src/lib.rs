@@ -6,4 +6,12 @@ pub mod character_controller;
 
 pub mod physics;
 
-pub mod camera;
\ No newline at end of file
+pub mod camera;
+
+pub mod spray;
+
+pub mod input;
+
+pub mod level;
+
+pub mod time;
\ No newline at end of file
@@ -5,13 +5,21 @@ use spraypaint::simple_scene::SimpleScenePlugin as simple_scene;
 use spraypaint::character_controller::CharacterControllerPlugin as character_controller;
 use spraypaint::physics::ExampleCommonPlugin as physics_plugin;
 use spraypaint::camera::CameraPlugin as camera_plugin;
+use spraypaint::spray::SprayPlugin as spray_plugin;
+use spraypaint::input::InputMapPlugin as input_plugin;
+use spraypaint::level::LevelPlugin as level_plugin;
+use spraypaint::time::ClockPlugin as clock_plugin;
 
 fn main() {
     App::new()
     .add_plugins(bevy_starter)
+    .add_plugins(clock_plugin)
+    .add_plugins(input_plugin)
     .add_plugins(simple_scene)
+    .add_plugins(level_plugin)
     .add_plugins(physics_plugin)
     .add_plugins(character_controller)
     .add_plugins(camera_plugin)
+    .add_plugins(spray_plugin)
     .run();
 }
\ No newline at end of file
@@ -1,10 +1,9 @@
 /// Copied from https://github.com/Jondolf/avian/blob/1c93e6c7d1194ea213293d49909e72f02cbdba64/crates/examples_common_3d/src/lib.rs#L1
 
 use avian3d::prelude::*;
-use bevy::{
-    input::common_conditions::input_just_pressed,
-    prelude::*,
-};
+use bevy::prelude::*;
+
+use crate::input::{GameAction, action_just_pressed};
 
 /// A plugin that adds common functionality used by examples,
 /// such as physics diagnostics UI and the ability to pause and step the simulation.
@@ -32,9 +31,10 @@ impl Plugin for ExampleCommonPlugin {
         app.add_systems(
             Update,
             (
-                toggle_diagnostics_ui.run_if(input_just_pressed(KeyCode::KeyU)),
-                toggle_paused.run_if(input_just_pressed(KeyCode::KeyP)),
-                step.run_if(physics_paused.and(input_just_pressed(KeyCode::Enter))),
+                toggle_diagnostics_ui
+                    .run_if(action_just_pressed(GameAction::ToggleDiagnosticsUi)),
+                toggle_paused.run_if(action_just_pressed(GameAction::TogglePhysicsPause)),
+                step.run_if(physics_paused.and(action_just_pressed(GameAction::StepPhysics))),
             ),
         );
     }
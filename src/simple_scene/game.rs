@@ -3,7 +3,20 @@
 use avian3d::{math::*, prelude::*};
 use bevy::{app::App, prelude::*};
 
-use crate::character_controller::CharacterControllerBundle;
+use crate::camera::{
+    CameraSystems,
+    effects::{FovKick, HeadBob, LandingDip, ScreenShake},
+    first_person::EyeOffset,
+    framing::{CameraTarget, StaticFraming},
+    rig::{CameraRig, DetachedRig},
+    third_person::SpringArm,
+};
+use crate::character_controller::{
+    CharacterControllerBundle, Climber, CollideAndSlide, Knockback, PushStrength, Sprint, Stances,
+};
+use crate::input::{ActionState, GameAction};
+use crate::spray::can::{SprayAim, SprayCan};
+use crate::time::{ClockDomain, Clocks, OnClock};
 
 const INITIAL_HEIGHT: f32 = 3.0;
 
@@ -12,15 +25,34 @@ pub enum CameraState {
     #[default]
     StaticView,
     FirstPersonView,
+    ThirdPersonView,
+    /// The [`MainCamera`] plays a camera path.
+    Cinematic,
 }
 
 impl CameraState{
-    fn toggle(&self) -> Self {
+    /// The state after this one when cycling through all of them.
+    fn next(&self) -> Self {
         match self {
             CameraState::StaticView => CameraState::FirstPersonView,
-            CameraState::FirstPersonView => CameraState::StaticView,
+            CameraState::FirstPersonView => CameraState::ThirdPersonView,
+            CameraState::ThirdPersonView | CameraState::Cinematic => CameraState::StaticView,
         }
     }
+
+    /// Whether the player controls the [`MainCharacter`] in this state.
+    pub fn controls_character(&self) -> bool {
+        matches!(self, CameraState::FirstPersonView | CameraState::ThirdPersonView)
+    }
+}
+
+/// Run condition that is true while the player controls the [`MainCharacter`], and not a
+/// detached camera.
+pub fn character_controlled(
+    state: Res<State<CameraState>>,
+    detached_cameras: Query<(), With<DetachedRig>>,
+) -> bool {
+    state.controls_character() && detached_cameras.is_empty()
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug, States, Default)]
@@ -35,11 +67,22 @@ pub(super) fn plugin(app: &mut App) {
     app
     .add_systems(Startup, (spawn_main_character))
     .add_systems(Startup, spawn_main_camera)
+    .add_systems(
+        Update,
+        aim_spray_from_main_camera.run_if(state_changed::<CameraState>),
+    )
     .init_state::<CameraState>()
     .init_state::<AppState>()
-    .add_systems(OnEnter(CameraState::StaticView), camera_static_view)
-    .add_systems(OnEnter(CameraState::FirstPersonView), player_translation_reset)
-    .add_systems(PostUpdate, camera_first_person_view.before(TransformSystem::TransformPropagate).run_if(in_state(CameraState::FirstPersonView)))
+    .add_systems(
+        PostUpdate,
+        (
+            // Without anything to frame, fall back to a fixed view.
+            camera_static_view.run_if(
+                in_state(CameraState::StaticView).and(not(any_with_component::<CameraTarget>)),
+            ),
+        )
+            .in_set(CameraSystems::Place),
+    )
     .add_systems(Update, (set_camera_state));
     println!("games plugin")
 }
@@ -51,7 +94,7 @@ pub struct MainCharacter;
 #[require(Camera3d)]
 pub struct MainCamera;
 
-pub fn spawn_main_character(mut commands: Commands) {
+pub fn spawn_main_character(mut commands: Commands, clocks: Res<Clocks>) {
     commands.spawn((
         MainCharacter,
         Transform::from_xyz(0.0, INITIAL_HEIGHT, 8.0).looking_at(Vec3::ZERO, Vec3::Y),
@@ -64,6 +107,16 @@ pub fn spawn_main_character(mut commands: Commands) {
         Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
         Restitution::ZERO.with_combine_rule(CoefficientCombine::Min),
         TransformInterpolation,
+        CollideAndSlide::default(),
+        PushStrength::default(),
+        Knockback::default(),
+        Stances::capsule(0.4, 1.0),
+        Sprint::default(),
+        Climber::default(),
+        EyeOffset::default(),
+        // Moves, and drips paint, at the speed of the physics simulation. Nested, as bundles
+        // hold at most 15 components.
+        (SprayCan::default(), OnClock(clocks.get(ClockDomain::Physics))),
         LockedAxes::from_bits(0b000_100)
         //GravityScale(0.0),
         )
@@ -74,20 +127,41 @@ pub fn spawn_main_camera(mut commands: Commands) {
         commands.spawn((
         Camera3d::default(),
         Transform::from_xyz(0.0, INITIAL_HEIGHT, 8.0).looking_at(Vec3::ZERO, Vec3::Y),
-        MainCamera,));
+        MainCamera,
+        StaticFraming::default(),
+        SpringArm::default(),
+        CameraRig::default(),
+        HeadBob::default(),
+        LandingDip::default(),
+        FovKick::default(),
+        ScreenShake::default(),));
 }
 
-fn set_camera_state(mut next_state: ResMut<NextState<CameraState>>, current_state: Res<State<CameraState>>, keyboard_input: Res<ButtonInput<KeyCode>>) {
-    if keyboard_input.just_released(KeyCode::Backspace) {
-        let camera_state = current_state.get();
-        next_state.set(current_state.toggle());
+/// Sprays the paint of the [`MainCharacter`] along the view of the [`MainCamera`] while the player
+/// controls it, and along the forward direction of the character otherwise.
+fn aim_spray_from_main_camera(
+    mut commands: Commands,
+    camera_state: Res<State<CameraState>>,
+    main_character: Single<Entity, With<MainCharacter>>,
+    main_camera: Single<Entity, With<MainCamera>>,
+) {
+    let mut main_character = commands.entity(*main_character);
+    if camera_state.controls_character() {
+        main_character.insert(SprayAim(*main_camera));
+    } else {
+        main_character.remove::<SprayAim>();
     }
 }
 
-fn camera_first_person_view(mut main_camera_query: Query<&mut Transform, With<MainCamera>>, main_character_query: Query<&Transform,(With<MainCharacter>, Without<MainCamera>)>){
-    if let Ok(mut main_camera_transform) = main_camera_query.single_mut() && 
-    let Ok(main_character_transform) = main_character_query.single(){   
-        main_camera_transform.translation = main_character_transform.translation;
+fn set_camera_state(mut next_state: ResMut<NextState<CameraState>>, current_state: Res<State<CameraState>>, action_state: Res<ActionState>) {
+    if action_state.just_released(GameAction::ToggleCameraState) {
+        let camera_state = current_state.get();
+        next_state.set(current_state.next());
+    } else if action_state.just_pressed(GameAction::PlayCinematic) {
+        next_state.set(match current_state.get() {
+            CameraState::Cinematic => CameraState::StaticView,
+            _ => CameraState::Cinematic,
+        });
     }
 }
 
@@ -96,11 +170,3 @@ fn camera_static_view(mut main_camera_query: Query<&mut Transform, With<MainCame
         *main_camera_transform = Transform::from_xyz(0.0, INITIAL_HEIGHT, 8.0).looking_at(Vec3::ZERO, Vec3::Y);
     }
 }
-
-
-fn player_translation_reset(main_camera_query: Query<&Transform, With<MainCamera>>, mut main_character_query: Query<&mut Transform,(With<MainCharacter>, Without<MainCamera>)>) {
-    if let Ok(main_camera_transform) = main_camera_query.single() && 
-    let Ok(mut main_character_transform) = main_character_query.single_mut(){   
-        main_character_transform.translation = main_camera_transform.translation;
-    }
-}
\ No newline at end of file
@@ -1,10 +1,8 @@
 use bevy::prelude::*;
 
-pub mod simple_scene;
 pub mod game;
 
 pub fn add_all_plugins(app: &mut App) {
-    app.add_plugins(simple_scene::plugin);
     app.add_plugins(game::plugin);
 }
 
@@ -0,0 +1,457 @@
+//! Spray cans, like the one carried by the [`MainCharacter`].
+//!
+//! Spraying is driven by [`SprayAction`] events, mirroring
+//! [`MovementAction`](crate::character_controller::MovementAction), so anything that can write
+//! events can spray with the can of any entity. Paint leaves the nozzle as a cone along the view of
+//! the can's owner, or of its [`SprayAim`]: close to a surface the paint lands in a small, dense
+//! spot, further away it spreads into a wide and faint mist.
+
+use std::f32::consts::{PI, TAU};
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use super::{
+    SpraySystems,
+    canvas::PaintBlendMode,
+    drip::{DripGravity, PaintDrip},
+    splat::PaintSplat,
+};
+use crate::bevy_starter::prelude::{GameRng, RngStream, random_number};
+use crate::input::{ActionState, GameAction, GameAxis};
+use crate::simple_scene::game::MainCharacter;
+use crate::time::{ClockTime, OnClock};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<SprayAction>()
+        .init_resource::<GameRng>()
+        .add_systems(Update, action_input.in_set(SpraySystems::Input))
+        .add_systems(Update, spray_paint.in_set(SpraySystems::Spray));
+}
+
+/// An event sent for an input action on the [`SprayCan`] of `owner`.
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub struct SprayAction {
+    /// The entity carrying the can.
+    pub owner: Entity,
+    pub kind: SprayActionKind,
+}
+
+/// What a [`SprayAction`] does with the can.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SprayActionKind {
+    /// Spray for this frame, with the trigger pressure in `0..=1`.
+    Spray(f32),
+    Refill,
+    CycleNozzle,
+}
+
+/// The shape of the spray leaving the can.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NozzleType {
+    /// Narrow cone and low flow, for thin lines.
+    Skinny,
+    #[default]
+    Standard,
+    /// Wide cone and high flow, for filling large areas.
+    Fat,
+}
+
+impl NozzleType {
+    /// Full opening angle of the spray cone, in radians.
+    pub fn cone_angle(self) -> f32 {
+        match self {
+            NozzleType::Skinny => 4.0_f32.to_radians(),
+            NozzleType::Standard => 10.0_f32.to_radians(),
+            NozzleType::Fat => 20.0_f32.to_radians(),
+        }
+    }
+
+    /// Paint leaving the can at full pressure, in millilitres per second.
+    pub fn flow_rate(self) -> f32 {
+        match self {
+            NozzleType::Skinny => 8.0,
+            NozzleType::Standard => 15.0,
+            NozzleType::Fat => 30.0,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            NozzleType::Skinny => NozzleType::Standard,
+            NozzleType::Standard => NozzleType::Fat,
+            NozzleType::Fat => NozzleType::Skinny,
+        }
+    }
+}
+
+/// A can of spray paint, sprayed along the forward direction of the entity carrying it.
+///
+/// Paint volumes are in millilitres and densities in millilitres per square meter of surface.
+#[derive(Component, Clone, Debug)]
+#[require(DripGravity)]
+pub struct SprayCan {
+    pub color: Color,
+    pub blend_mode: PaintBlendMode,
+    pub nozzle: NozzleType,
+    /// Paint left in the can.
+    pub paint: f32,
+    pub capacity: f32,
+    /// Surfaces further away than this are out of reach of the spray.
+    pub max_distance: f32,
+    pub splats_per_second: f32,
+    /// Paint density at which a splat is fully opaque. Thinner coats are translucent.
+    pub opaque_density: f32,
+    /// Paint density beyond which the surplus runs off as drips.
+    pub drip_density: f32,
+    pending_splats: f32,
+    surplus_paint: f32,
+}
+
+impl Default for SprayCan {
+    fn default() -> Self {
+        Self {
+            color: Color::srgb_u8(230, 40, 90),
+            blend_mode: PaintBlendMode::Over,
+            nozzle: NozzleType::Standard,
+            paint: 400.0,
+            capacity: 400.0,
+            max_distance: 6.0,
+            splats_per_second: 60.0,
+            opaque_density: 20.0,
+            drip_density: 150.0,
+            pending_splats: 0.0,
+            surplus_paint: 0.0,
+        }
+    }
+}
+
+impl SprayCan {
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_nozzle(mut self, nozzle: NozzleType) -> Self {
+        self.nozzle = nozzle;
+        self
+    }
+
+    pub fn refill(&mut self) {
+        self.paint = self.capacity;
+    }
+
+    /// Fraction of the can that is still full, in `0..=1`.
+    pub fn fill_level(&self) -> f32 {
+        if self.capacity > 0.0 {
+            (self.paint / self.capacity).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Makes a [`SprayCan`] spray along the view of another entity, like a camera, instead of the
+/// forward direction of its owner.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SprayAim(pub Entity);
+
+/// Paint collected as surplus before it runs off as one drip.
+const DRIP_VOLUME: f32 = 3.0;
+/// Spots never get smaller than this, however close the nozzle is.
+const MIN_SPOT_RADIUS: f32 = 0.02;
+/// Radius of one splat relative to the spot the cone covers on the surface.
+const SPLAT_SPOT_RATIO: f32 = 0.5;
+/// Upper bound on splats per can and frame, so a long frame doesn't stall on raycasts.
+const MAX_SPLATS_PER_FRAME: f32 = 8.0;
+
+/// Sends [`SprayAction`] events for the can of the [`MainCharacter`] based on the
+/// [`ActionState`].
+fn action_input(
+    mut spray_event_writer: EventWriter<SprayAction>,
+    action_state: Res<ActionState>,
+    characters: Query<Entity, With<MainCharacter>>,
+) {
+    for owner in &characters {
+        let mut send = |kind| {
+            spray_event_writer.write(SprayAction { owner, kind });
+        };
+
+        // Analog triggers give partial pressure, buttons full pressure.
+        let pressure = action_state.axis(GameAxis::SprayPressure);
+        if pressure > 0.0 {
+            send(SprayActionKind::Spray(pressure));
+        }
+
+        if action_state.just_pressed(GameAction::Refill) {
+            send(SprayActionKind::Refill);
+        }
+
+        if action_state.just_pressed(GameAction::CycleNozzle) {
+            send(SprayActionKind::CycleNozzle);
+        }
+    }
+}
+
+/// Responds to [`SprayAction`] events, spraying paint along the view of each can's owner.
+pub(super) fn spray_paint(
+    mut commands: Commands,
+    clock: ClockTime,
+    mut spray_event_reader: EventReader<SprayAction>,
+    spatial_query: SpatialQuery,
+    transforms: Query<&GlobalTransform>,
+    mut cans: Query<(
+        &mut SprayCan,
+        &DripGravity,
+        Option<&OnClock>,
+        Option<&SprayAim>,
+    )>,
+    mut rng: ResMut<GameRng>,
+) {
+    // Kept in the order of the events, so the scatter of several cans is drawn deterministically.
+    let mut pressures: Vec<(Entity, f32)> = Vec::new();
+    for event in spray_event_reader.read() {
+        let Ok((mut can, ..)) = cans.get_mut(event.owner) else {
+            continue;
+        };
+        match event.kind {
+            SprayActionKind::Spray(event_pressure) => {
+                let event_pressure = event_pressure.clamp(0.0, 1.0);
+                match pressures
+                    .iter_mut()
+                    .find(|(owner, _)| *owner == event.owner)
+                {
+                    Some((_, pressure)) => *pressure = pressure.max(event_pressure),
+                    None => pressures.push((event.owner, event_pressure)),
+                }
+            }
+            SprayActionKind::Refill => can.refill(),
+            SprayActionKind::CycleNozzle => can.nozzle = can.nozzle.next(),
+        }
+    }
+
+    for (owner, pressure) in pressures {
+        if pressure <= 0.0 {
+            continue;
+        }
+        let Ok((mut can, drip_gravity, on_clock, aim)) = cans.get_mut(owner) else {
+            continue;
+        };
+        let Ok(aim_transform) = transforms.get(aim.map_or(owner, |aim| aim.0)) else {
+            continue;
+        };
+        // Don't paint the owner, even when aiming from a camera sitting inside it.
+        let filter = SpatialQueryFilter::from_excluded_entities([owner]);
+
+        let can = &mut *can;
+        let half_angle = can.nozzle.cone_angle() / 2.0;
+        let splat_volume = can.nozzle.flow_rate() * pressure / can.splats_per_second;
+
+        let new_splats = clock.delta_secs(owner) * can.splats_per_second;
+        can.pending_splats = (can.pending_splats + new_splats).min(MAX_SPLATS_PER_FRAME);
+
+        while can.pending_splats >= 1.0 && can.paint > 0.0 {
+            can.pending_splats -= 1.0;
+            let volume = splat_volume.min(can.paint);
+            can.paint -= volume;
+
+            // Pick a direction within the cone, spread evenly over its cross-section.
+            let scatter = rng.stream(RngStream::SprayScatter);
+            let spread = half_angle * random_number(scatter, 0.0, 1.0).sqrt();
+            let around = random_number(scatter, 0.0, TAU);
+            let local_direction = Vec3::new(
+                spread.sin() * around.cos(),
+                spread.sin() * around.sin(),
+                -spread.cos(),
+            );
+            let Ok(direction) = Dir3::new(aim_transform.rotation() * local_direction) else {
+                continue;
+            };
+
+            let Some(hit) = spatial_query.cast_ray(
+                aim_transform.translation(),
+                direction,
+                can.max_distance,
+                true,
+                &filter,
+            ) else {
+                continue;
+            };
+            let Ok(surface_transform) = transforms.get(hit.entity) else {
+                continue;
+            };
+
+            // The further away the surface, the wider and thinner the paint is spread.
+            let spot_radius = (hit.distance * half_angle.tan()).max(MIN_SPOT_RADIUS);
+            let radius = spot_radius * SPLAT_SPOT_RATIO;
+            let area = PI * radius * radius;
+            let density = volume / area;
+            let alpha = (density / can.opaque_density).min(1.0) * can.color.alpha();
+            can.surplus_paint += (density - can.drip_density).max(0.0) * area;
+
+            let splat = PaintSplat::from_world_hit(
+                hit.entity,
+                surface_transform,
+                aim_transform.translation() + direction * hit.distance,
+                hit.normal,
+                can.color.with_alpha(alpha),
+                radius,
+                can.blend_mode,
+            );
+
+            if can.surplus_paint >= DRIP_VOLUME {
+                can.surplus_paint -= DRIP_VOLUME;
+                let mut drip = commands.spawn((
+                    PaintDrip::new(&splat, can.color, DRIP_VOLUME),
+                    *drip_gravity,
+                ));
+                if let Some(on_clock) = on_clock {
+                    drip.insert(*on_clock);
+                }
+            }
+
+            commands.spawn(splat.into_bundle());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    /// Frames and splats per second, so a can sprays exactly one splat per frame.
+    const RATE: f32 = 64.0;
+    /// Paint leaving a standard nozzle for one splat at full pressure.
+    const SPLAT_VOLUME: f32 = 15.0 / RATE;
+
+    fn one_splat_per_frame() -> SprayCan {
+        SprayCan {
+            splats_per_second: RATE,
+            ..default()
+        }
+    }
+
+    /// Sprays `can` at `pressure` for `frames` frames at a wall `distance` in front of it, and
+    /// returns the can and the splats left on the wall, in the local space of the wall.
+    fn spray(
+        can: SprayCan,
+        distance: f32,
+        pressure: f32,
+        frames: usize,
+    ) -> (SprayCan, Vec<PaintSplat>) {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            AssetPlugin::default(),
+            PhysicsPlugins::default(),
+            plugin,
+        ))
+        .init_asset::<Mesh>()
+        .init_resource::<ActionState>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / RATE as f64,
+        )));
+        // Its front face is `distance` along the forward direction of the can.
+        let center = Vec3::NEG_Z * (distance + 0.5);
+        app.world_mut().spawn((
+            RigidBody::Static,
+            Collider::cuboid(20.0, 20.0, 1.0),
+            Position(center),
+            Transform::from_translation(center),
+        ));
+        let owner = app.world_mut().spawn((can, Transform::default())).id();
+        // Let the wall reach the spatial query first.
+        app.update();
+
+        for _ in 0..frames {
+            app.world_mut().send_event(SprayAction {
+                owner,
+                kind: SprayActionKind::Spray(pressure),
+            });
+            app.update();
+        }
+
+        let can = app.world().get::<SprayCan>(owner).unwrap().clone();
+        let splats = app
+            .world_mut()
+            .query::<&PaintSplat>()
+            .iter(app.world())
+            .cloned()
+            .collect();
+        (can, splats)
+    }
+
+    /// How far a splat landed from the axis of the spray cone.
+    fn spread(splat: &PaintSplat) -> f32 {
+        splat.local_position.truncate().length()
+    }
+
+    #[test]
+    fn nozzles_spread_paint_over_their_cone() {
+        let distance = 2.0;
+        let mut widest = Vec::new();
+        for nozzle in [NozzleType::Skinny, NozzleType::Standard, NozzleType::Fat] {
+            let (_, splats) = spray(one_splat_per_frame().with_nozzle(nozzle), distance, 1.0, 30);
+            assert_eq!(splats.len(), 30);
+
+            let cone_radius = distance * (nozzle.cone_angle() / 2.0).tan();
+            let spreads = splats.iter().map(spread);
+            let max_spread = spreads.fold(0.0, f32::max);
+            assert!(max_spread <= cone_radius * 1.01, "{nozzle:?}: {max_spread}");
+            // Thirty splats spread evenly over the cone don't all land close to its axis.
+            assert!(max_spread > cone_radius * 0.5, "{nozzle:?}: {max_spread}");
+            // Splats off the axis land a little further away, and spread a little wider.
+            for splat in &splats {
+                let radius = cone_radius.max(MIN_SPOT_RADIUS) * SPLAT_SPOT_RATIO;
+                assert!((splat.radius - radius).abs() < radius * 0.02);
+            }
+            widest.push(max_spread);
+        }
+        assert!(widest[0] < widest[1] && widest[1] < widest[2], "{widest:?}");
+    }
+
+    #[test]
+    fn spraying_uses_up_paint_by_flow_and_pressure() {
+        let (can, splats) = spray(one_splat_per_frame(), 2.0, 1.0, 64);
+        assert!((can.paint - (can.capacity - 15.0)).abs() < 1e-3);
+        assert_eq!(splats.len(), 64);
+
+        // Half pressure lets half as much paint through.
+        let (can, _) = spray(one_splat_per_frame(), 2.0, 0.5, 64);
+        assert!((can.paint - (can.capacity - 7.5)).abs() < 1e-3);
+
+        // An almost empty can sprays what is left, then stops.
+        let almost_empty = SprayCan {
+            paint: 3.5 * SPLAT_VOLUME,
+            ..one_splat_per_frame()
+        };
+        let (can, splats) = spray(almost_empty, 2.0, 1.0, 64);
+        assert_eq!(can.paint, 0.0);
+        assert_eq!(splats.len(), 4);
+        assert_eq!(can.fill_level(), 0.0);
+    }
+
+    #[test]
+    fn paint_spreads_thinner_further_away() {
+        let (can, near) = spray(one_splat_per_frame(), 1.0, 1.0, 10);
+        let (_, far) = spray(one_splat_per_frame(), 4.0, 1.0, 10);
+        let alpha = |splat: &PaintSplat| {
+            let density = SPLAT_VOLUME / (PI * splat.radius * splat.radius);
+            (density / can.opaque_density).min(1.0)
+        };
+
+        for (near, far) in near.iter().zip(&far) {
+            // The spot grows with the distance, so the same paint covers it more thinly.
+            assert!((far.radius / near.radius - 4.0).abs() < 0.1);
+            assert!((near.color.alpha() - alpha(near)).abs() < 1e-3);
+            assert!((far.color.alpha() - alpha(far)).abs() < 1e-3);
+            assert_eq!(near.color.alpha(), 1.0);
+            assert!(far.color.alpha() < 0.5, "{}", far.color.alpha());
+        }
+    }
+}
@@ -0,0 +1,708 @@
+//! CPU-side paint canvases that accumulate splats into one texture per surface.
+//!
+//! A [`PaintCanvas`] keeps a paint layer as 8 bit sRGB with straight alpha, blended in linear
+//! color. Splats landing on a surface with a canvas are stamped into it through the surface's
+//! [`PaintUvLayout`] and then despawned. When render assets exist the canvas is composited over
+//! the surface's original base color, and the touched region is queued in [`CanvasUploads`] and
+//! written straight into the material's base color texture on the GPU, without re-uploading the
+//! rest of the image.
+
+use bevy::{
+    math::URect,
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSet,
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        mesh::VertexAttributeValues,
+        render_asset::{RenderAssetUsages, RenderAssets},
+        render_resource::{
+            Extent3d, Origin3d, TexelCopyBufferLayout, TexelCopyTextureInfo, TextureAspect,
+            TextureDimension, TextureFormat,
+        },
+        renderer::RenderQueue,
+        texture::GpuImage,
+    },
+};
+
+use super::{
+    SpraySystems,
+    coverage::PaintCoverage,
+    splat::{PaintSplat, attach_splat_visuals},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<CanvasUploads>()
+        .add_plugins(ExtractResourcePlugin::<CanvasUploads>::default())
+        .add_systems(
+            Update,
+            (
+                stamp_splats.before(attach_splat_visuals),
+                (setup_canvas_textures, upload_dirty_canvases)
+                    .chain()
+                    .after(stamp_splats)
+                    .run_if(resource_exists::<Assets<StandardMaterial>>),
+            )
+                .in_set(SpraySystems::Apply),
+        );
+
+    if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+        render_app.add_systems(
+            Render,
+            write_canvas_uploads.in_set(RenderSet::PrepareResources),
+        );
+    }
+}
+
+/// How a splat is combined with the paint already on a canvas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaintBlendMode {
+    /// Paints on top of existing paint, respecting the alpha of the new color.
+    #[default]
+    Over,
+    /// Tints paint that is already on the surface. Bare surface is left alone.
+    Multiply,
+    /// Removes existing paint, scaled by the alpha of the splat color.
+    Erase,
+}
+
+/// Maps points on a surface to the texture coordinates of its canvas.
+///
+/// Each face of the shape gets its own cell of the canvas. Within a cell the coordinates follow
+/// the UVs of the matching Bevy primitive mesh, so [`PaintUvLayout::remap_mesh_uvs`] only has to
+/// move the original UVs into their cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PaintUvLayout {
+    /// A box with the given full size, laid out as a 3x2 grid of faces.
+    Cuboid { size: Vec3 },
+    /// A capped cylinder. The top cap takes the left half of the canvas, the bottom cap and the
+    /// barrel share the right half.
+    Cylinder { radius: f32, height: f32 },
+}
+
+/// A point located on a face of a [`PaintUvLayout`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FacePoint {
+    pub face: usize,
+    /// Coordinates within the face, in `0..=1`.
+    pub uv: Vec2,
+}
+
+impl PaintUvLayout {
+    /// The +Z face of a [`PaintUvLayout::Cuboid`], which faces the default camera.
+    pub const CUBOID_FRONT: usize = 4;
+    /// The top cap of a [`PaintUvLayout::Cylinder`].
+    pub const CYLINDER_TOP: usize = 0;
+
+    pub fn face_count(&self) -> usize {
+        match self {
+            PaintUvLayout::Cuboid { .. } => 6,
+            PaintUvLayout::Cylinder { .. } => 3,
+        }
+    }
+
+    /// The region of the canvas used by `face`, in normalized canvas coordinates.
+    pub fn face_cell(&self, face: usize) -> Rect {
+        match self {
+            // Faces are ordered +X, -X, +Y, -Y, +Z, -Z.
+            PaintUvLayout::Cuboid { .. } => {
+                let min = Vec2::new((face % 3) as f32 / 3.0, (face / 3) as f32 / 2.0);
+                Rect::from_corners(min, min + Vec2::new(1.0 / 3.0, 0.5))
+            }
+            // Faces are ordered top, bottom, barrel.
+            PaintUvLayout::Cylinder { .. } => match face {
+                0 => Rect::new(0.0, 0.0, 0.5, 1.0),
+                1 => Rect::new(0.5, 0.0, 1.0, 0.5),
+                _ => Rect::new(0.5, 0.5, 1.0, 1.0),
+            },
+        }
+    }
+
+    /// The size of `face` in local units along its U and V directions.
+    pub fn face_extent(&self, face: usize) -> Vec2 {
+        match *self {
+            PaintUvLayout::Cuboid { size } => match face {
+                0 | 1 => Vec2::new(size.y, size.z),
+                2 | 3 => Vec2::new(size.x, size.z),
+                _ => Vec2::new(size.x, size.y),
+            },
+            PaintUvLayout::Cylinder { radius, height } => match face {
+                0 | 1 => Vec2::splat(2.0 * radius),
+                _ => Vec2::new(std::f32::consts::TAU * radius, height),
+            },
+        }
+    }
+
+    /// Whether a point within the cell of `face` lies on the surface. The corners of a cap's
+    /// cell are outside of the circle.
+    pub fn face_contains(&self, face: usize, uv: Vec2) -> bool {
+        match self {
+            PaintUvLayout::Cylinder { .. } if face < 2 => {
+                uv.distance_squared(Vec2::splat(0.5)) <= 0.25
+            }
+            _ => true,
+        }
+    }
+
+    /// The face a vertex or hit with the given local normal belongs to.
+    pub fn face_for_normal(&self, normal: Vec3) -> usize {
+        match self {
+            PaintUvLayout::Cuboid { .. } => {
+                let abs = normal.abs();
+                let (axis, positive) = if abs.x >= abs.y && abs.x >= abs.z {
+                    (0, normal.x >= 0.0)
+                } else if abs.y >= abs.z {
+                    (1, normal.y >= 0.0)
+                } else {
+                    (2, normal.z >= 0.0)
+                };
+                axis * 2 + usize::from(!positive)
+            }
+            PaintUvLayout::Cylinder { .. } => {
+                if normal.y > 0.5 {
+                    0
+                } else if normal.y < -0.5 {
+                    1
+                } else {
+                    2
+                }
+            }
+        }
+    }
+
+    /// Locates a local-space point with its local normal on one of the faces.
+    pub fn locate(&self, point: Vec3, normal: Vec3) -> FacePoint {
+        let (face, uv) = self.face_uv(point, normal);
+        FacePoint {
+            face,
+            uv: uv.clamp(Vec2::ZERO, Vec2::ONE),
+        }
+    }
+
+    /// Whether a local-space point lies within the bounds of the face its local normal belongs to.
+    pub fn on_face(&self, point: Vec3, normal: Vec3) -> bool {
+        let (face, uv) = self.face_uv(point, normal);
+        uv.cmpge(Vec2::ZERO).all() && uv.cmple(Vec2::ONE).all() && self.face_contains(face, uv)
+    }
+
+    /// The face of a local-space point, with its coordinates on the face. The coordinates are
+    /// outside of `0..=1` for points beyond the edges of the face.
+    fn face_uv(&self, point: Vec3, normal: Vec3) -> (usize, Vec2) {
+        let face = self.face_for_normal(normal);
+        let uv = match *self {
+            PaintUvLayout::Cuboid { size } => {
+                let min = -size / 2.0;
+                let max = size / 2.0;
+                match face {
+                    0 => Vec2::new((point.y - min.y) / size.y, (point.z - min.z) / size.z),
+                    1 => Vec2::new((max.y - point.y) / size.y, (max.z - point.z) / size.z),
+                    2 => Vec2::new((point.x - min.x) / size.x, (point.z - min.z) / size.z),
+                    3 => Vec2::new((max.x - point.x) / size.x, (max.z - point.z) / size.z),
+                    4 => Vec2::new((point.x - min.x) / size.x, (point.y - min.y) / size.y),
+                    _ => Vec2::new((max.x - point.x) / size.x, (max.y - point.y) / size.y),
+                }
+            }
+            PaintUvLayout::Cylinder { radius, height } => match face {
+                0 | 1 => Vec2::new(
+                    0.5 * (point.x / radius + 1.0),
+                    1.0 - 0.5 * (point.z / radius + 1.0),
+                ),
+                _ => Vec2::new(
+                    point.z.atan2(point.x).rem_euclid(std::f32::consts::TAU)
+                        / std::f32::consts::TAU,
+                    point.y / height + 0.5,
+                ),
+            },
+        };
+        (face, uv)
+    }
+
+    /// Moves the UVs of a mesh built from the matching Bevy primitive into the face cells of
+    /// this layout, so the canvas texture lines up with [`PaintUvLayout::locate`].
+    pub fn remap_mesh_uvs(&self, mesh: &mut Mesh) {
+        let Some(VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL).cloned()
+        else {
+            warn!("Paint canvas mesh has no normals, leaving its UVs untouched");
+            return;
+        };
+        let Some(VertexAttributeValues::Float32x2(uvs)) = mesh.attribute_mut(Mesh::ATTRIBUTE_UV_0)
+        else {
+            warn!("Paint canvas mesh has no UVs, leaving them untouched");
+            return;
+        };
+
+        for (uv, normal) in uvs.iter_mut().zip(normals) {
+            let cell = self.face_cell(self.face_for_normal(Vec3::from(normal)));
+            *uv = (cell.min + Vec2::from(*uv) * cell.size()).to_array();
+        }
+    }
+}
+
+/// The most texels a single [`PaintCanvas`] may have, which is 4 MB of paint on the CPU and as
+/// much again on the GPU.
+pub const MAX_CANVAS_TEXELS: u32 = 1 << 20;
+
+/// A paint layer owned by a surface, sized in texels.
+#[derive(Component, Clone, Debug)]
+#[require(PaintCoverage)]
+pub struct PaintCanvas {
+    layout: PaintUvLayout,
+    size: UVec2,
+    /// sRGB RGBA8 with straight alpha, a quarter of the size of float colors.
+    pixels: Vec<[u8; 4]>,
+    /// The color of the bare surface that paint is composited over when uploading.
+    base_color: LinearRgba,
+    /// The region changed since the last upload. `max` is exclusive.
+    dirty: Option<URect>,
+    /// The region changed since coverage was last measured. `max` is exclusive.
+    unmeasured: Option<URect>,
+}
+
+impl PaintCanvas {
+    pub fn new(layout: PaintUvLayout, resolution: UVec2) -> Self {
+        let resolution = resolution.max(UVec2::ONE);
+        Self {
+            layout,
+            size: resolution,
+            pixels: vec![[0; 4]; (resolution.x * resolution.y) as usize],
+            base_color: LinearRgba::WHITE,
+            dirty: None,
+            unmeasured: Some(URect::from_corners(UVec2::ZERO, resolution)),
+        }
+    }
+
+    pub fn layout(&self) -> PaintUvLayout {
+        self.layout
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// The texels covered by the cell of `face`. `max` is exclusive, and the cells of different
+    /// faces never overlap.
+    pub fn face_texels(&self, face: usize) -> URect {
+        let cell = self.layout.face_cell(face);
+        let size = self.size.as_vec2();
+        URect::from_corners(
+            (cell.min * size).round().as_uvec2(),
+            (cell.max * size).round().as_uvec2().min(self.size),
+        )
+    }
+
+    /// The paint at a texel, or [`LinearRgba::NONE`] outside the canvas or where nothing was painted.
+    pub fn pixel(&self, texel: UVec2) -> LinearRgba {
+        if texel.x >= self.size.x || texel.y >= self.size.y {
+            return LinearRgba::NONE;
+        }
+        self.paint((texel.y * self.size.x + texel.x) as usize)
+    }
+
+    /// All texels as sRGB RGBA8 with straight alpha, row by row.
+    pub fn pixels(&self) -> &[[u8; 4]] {
+        &self.pixels
+    }
+
+    fn paint(&self, index: usize) -> LinearRgba {
+        Srgba::from_u8_array(self.pixels[index]).into()
+    }
+
+    /// The texel that a local-space point on the surface maps to.
+    pub fn texel_at(&self, local_position: Vec3, local_normal: Vec3) -> UVec2 {
+        let point = self.layout.locate(local_position, local_normal);
+        let cell = self.layout.face_cell(point.face);
+        let texel = (cell.min + point.uv * cell.size()) * self.size.as_vec2();
+        let cell_texels = self.face_texels(point.face);
+        texel
+            .as_uvec2()
+            .min(cell_texels.max.saturating_sub(UVec2::ONE))
+            .max(cell_texels.min)
+            .min(self.size - 1)
+    }
+
+    /// The paint at a local-space point on the surface.
+    pub fn sample(&self, local_position: Vec3, local_normal: Vec3) -> LinearRgba {
+        self.pixel(self.texel_at(local_position, local_normal))
+    }
+
+    /// Stamps a disc of paint with a radius in local units onto the surface.
+    pub fn stamp(
+        &mut self,
+        local_position: Vec3,
+        local_normal: Vec3,
+        radius: f32,
+        color: impl Into<LinearRgba>,
+        blend_mode: PaintBlendMode,
+    ) {
+        let color = color.into();
+        let point = self.layout.locate(local_position, local_normal);
+        let cell = self.layout.face_cell(point.face);
+        let size = self.size.as_vec2();
+
+        // Paint never bleeds across into the cell of another face.
+        let cell_texels = self.face_texels(point.face);
+
+        let center = (cell.min + point.uv * cell.size()) * size;
+        // Texels per local unit can differ between U and V, which turns the disc into an ellipse.
+        let radius_texels = radius * cell.size() * size / self.layout.face_extent(point.face);
+        if radius_texels.min_element() <= 0.0 {
+            return;
+        }
+
+        let min = (center - radius_texels)
+            .floor()
+            .max(Vec2::ZERO)
+            .as_uvec2()
+            .max(cell_texels.min);
+        let max = (center + radius_texels)
+            .ceil()
+            .as_uvec2()
+            .min(cell_texels.max);
+        if min.x >= max.x || min.y >= max.y {
+            return;
+        }
+
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                let offset = (Vec2::new(x as f32, y as f32) + 0.5 - center) / radius_texels;
+                if offset.length_squared() > 1.0 {
+                    continue;
+                }
+                let index = (y * self.size.x + x) as usize;
+                let blended = blend(self.paint(index), color, blend_mode);
+                self.pixels[index] = Srgba::from(blended).to_u8_array();
+            }
+        }
+
+        self.mark_dirty(URect::from_corners(min, max));
+    }
+
+    /// Removes all paint.
+    pub fn clear(&mut self) {
+        self.pixels.fill([0; 4]);
+        self.mark_dirty(URect::from_corners(UVec2::ZERO, self.size));
+    }
+
+    fn mark_dirty(&mut self, rect: URect) {
+        self.dirty = Some(match self.dirty {
+            Some(dirty) => dirty.union(rect),
+            None => rect,
+        });
+        self.unmeasured = Some(match self.unmeasured {
+            Some(unmeasured) => unmeasured.union(rect),
+            None => rect,
+        });
+    }
+
+    /// Takes the region changed since coverage was last measured, leaving nothing unmeasured.
+    pub(super) fn take_unmeasured(&mut self) -> Option<URect> {
+        self.unmeasured.take()
+    }
+
+    /// The paint layer composited over the bare surface color.
+    fn composited(&self, index: usize) -> LinearRgba {
+        let paint = self.paint(index);
+        let mut color = self.base_color.mix(&paint.with_alpha(1.0), paint.alpha);
+        color.alpha = self.base_color.alpha;
+        color
+    }
+
+    /// The composited texels of `rect` as sRGB RGBA8 bytes, row by row.
+    fn composited_bytes(&self, rect: URect) -> Vec<u8> {
+        let mut data = Vec::with_capacity((rect.width() * rect.height() * 4) as usize);
+        for y in rect.min.y..rect.max.y {
+            for x in rect.min.x..rect.max.x {
+                let index = (y * self.size.x + x) as usize;
+                data.extend(Color::from(self.composited(index)).to_srgba().to_u8_array());
+            }
+        }
+        data
+    }
+}
+
+fn blend(destination: LinearRgba, source: LinearRgba, blend_mode: PaintBlendMode) -> LinearRgba {
+    match blend_mode {
+        PaintBlendMode::Over => {
+            let alpha = source.alpha + destination.alpha * (1.0 - source.alpha);
+            if alpha <= 0.0 {
+                return LinearRgba::NONE;
+            }
+            let source_weight = source.alpha / alpha;
+            destination
+                .with_alpha(1.0)
+                .mix(&source.with_alpha(1.0), source_weight)
+                .with_alpha(alpha)
+        }
+        PaintBlendMode::Multiply => {
+            let tinted = LinearRgba::new(
+                destination.red * source.red,
+                destination.green * source.green,
+                destination.blue * source.blue,
+                destination.alpha,
+            );
+            destination.mix(&tinted, source.alpha)
+        }
+        PaintBlendMode::Erase => destination.with_alpha(destination.alpha * (1.0 - source.alpha)),
+    }
+}
+
+/// The texture a [`PaintCanvas`] is uploaded to.
+#[derive(Component, Clone, Debug)]
+pub struct PaintCanvasTexture(pub Handle<Image>);
+
+/// Dirty regions of canvases waiting to be written into their textures.
+///
+/// Filled every frame in the main world and extracted to the render world, where each region is
+/// written into the existing GPU texture. The [`Image`] asset itself is never touched after it is
+/// created, since changing it would upload the whole image again.
+#[derive(Resource, Clone, Debug, Default, ExtractResource)]
+pub struct CanvasUploads(pub Vec<CanvasUpload>);
+
+/// A region of a canvas texture with its new texels.
+#[derive(Clone, Debug)]
+pub struct CanvasUpload {
+    pub image: AssetId<Image>,
+    /// The region of the texture. `max` is exclusive.
+    pub rect: URect,
+    /// The composited texels of the region as sRGB RGBA8 bytes, row by row.
+    pub data: Vec<u8>,
+}
+
+/// Stamps new splats into the canvas of the surface they landed on.
+///
+/// Splats on surfaces without a canvas are left alone and keep their own entity.
+pub(super) fn stamp_splats(
+    mut commands: Commands,
+    splats: Query<(Entity, &PaintSplat), Added<PaintSplat>>,
+    mut canvases: Query<&mut PaintCanvas>,
+) {
+    for (entity, splat) in &splats {
+        let Ok(mut canvas) = canvases.get_mut(splat.surface) else {
+            continue;
+        };
+        canvas.stamp(
+            splat.local_position,
+            splat.local_normal,
+            splat.radius,
+            splat.color,
+            splat.blend_mode,
+        );
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Gives canvases a texture, and points the surface material and mesh at it.
+///
+/// The mesh and material are copied first so other entities sharing them are not affected.
+/// Canvases whose material isn't loaded yet are retried every frame until it is.
+fn setup_canvas_textures(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut canvases: Query<
+        (
+            Entity,
+            &mut PaintCanvas,
+            &MeshMaterial3d<StandardMaterial>,
+            Option<&Mesh3d>,
+        ),
+        Without<PaintCanvasTexture>,
+    >,
+) {
+    for (entity, mut canvas, material, mesh) in &mut canvases {
+        let Some(mut material) = materials.get(&material.0).cloned() else {
+            continue;
+        };
+        canvas.base_color = material.base_color.into();
+
+        // The image starts out with whatever was painted before the texture existed. Later
+        // changes go through `CanvasUploads`, so the main world doesn't need to keep the image.
+        let size = canvas.size;
+        let image = images.add(Image::new(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            canvas.composited_bytes(URect::from_corners(UVec2::ZERO, size)),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::RENDER_WORLD,
+        ));
+        canvas.dirty = None;
+
+        material.base_color = Color::WHITE;
+        material.base_color_texture = Some(image.clone());
+        commands.entity(entity).insert((
+            MeshMaterial3d(materials.add(material)),
+            PaintCanvasTexture(image),
+        ));
+
+        if let Some(mut mesh) = mesh.and_then(|mesh| meshes.get(&mesh.0)).cloned() {
+            canvas.layout.remap_mesh_uvs(&mut mesh);
+            commands.entity(entity).insert(Mesh3d(meshes.add(mesh)));
+        }
+    }
+}
+
+/// Queues the dirty region of each canvas to be written into its texture.
+fn upload_dirty_canvases(
+    mut uploads: ResMut<CanvasUploads>,
+    mut canvases: Query<(&mut PaintCanvas, &PaintCanvasTexture)>,
+) {
+    // Uploads from the last frame were already extracted. Leave an empty queue unchanged, so it
+    // isn't extracted again for nothing.
+    if !uploads.0.is_empty() {
+        uploads.0.clear();
+    }
+
+    for (mut canvas, texture) in &mut canvases {
+        let Some(dirty) = canvas.dirty else {
+            continue;
+        };
+        if dirty.is_empty() {
+            continue;
+        }
+        uploads.0.push(CanvasUpload {
+            image: texture.0.id(),
+            rect: dirty,
+            data: canvas.composited_bytes(dirty),
+        });
+        // Only the paint itself counts as a change to the canvas.
+        canvas.bypass_change_detection().dirty = None;
+    }
+}
+
+/// Writes the queued canvas regions into their textures on the GPU.
+fn write_canvas_uploads(
+    mut uploads: ResMut<CanvasUploads>,
+    gpu_images: Res<RenderAssets<GpuImage>>,
+    render_queue: Res<RenderQueue>,
+) {
+    // A texture that isn't on the GPU yet is created from its image, which already holds the
+    // paint, so its uploads can be dropped.
+    for upload in uploads.0.drain(..) {
+        let Some(gpu_image) = gpu_images.get(upload.image) else {
+            continue;
+        };
+        let size = upload.rect.size();
+        render_queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &gpu_image.texture,
+                mip_level: 0,
+                origin: Origin3d {
+                    x: upload.rect.min.x,
+                    y: upload.rect.min.y,
+                    z: 0,
+                },
+                aspect: TextureAspect::All,
+            },
+            &upload.data,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.x * 4),
+                rows_per_image: None,
+            },
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: Srgba = Srgba::rgb(0.0, 0.0, 1.0);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default(), plugin))
+            .init_asset::<Image>()
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>();
+        app
+    }
+
+    fn texel(upload: &CanvasUpload, texel: UVec2) -> [u8; 4] {
+        let offset = texel - upload.rect.min;
+        let index = ((offset.y * upload.rect.width() + offset.x) * 4) as usize;
+        upload.data[index..index + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn splats_are_read_back_from_the_uploaded_region() {
+        let mut app = app();
+        let surface = app
+            .world_mut()
+            .spawn((
+                PaintCanvas::new(
+                    PaintUvLayout::Cuboid {
+                        size: Vec3::splat(3.0),
+                    },
+                    UVec2::new(48, 32),
+                ),
+                MeshMaterial3d::<StandardMaterial>(Handle::default()),
+            ))
+            .id();
+
+        // The material isn't there yet, so the canvas waits for it.
+        app.update();
+        assert!(app.world().get::<PaintCanvasTexture>(surface).is_none());
+
+        let material = app
+            .world_mut()
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial::from(Color::from(BASE)));
+        app.world_mut()
+            .entity_mut(surface)
+            .insert(MeshMaterial3d(material));
+        app.update();
+
+        let texture = app
+            .world()
+            .get::<PaintCanvasTexture>(surface)
+            .expect("canvas should get a texture once its material is loaded")
+            .0
+            .clone();
+        let image = app
+            .world()
+            .resource::<Assets<Image>>()
+            .get(&texture)
+            .unwrap();
+        let data = image.data.as_ref().unwrap();
+        assert!(
+            data.chunks(4)
+                .all(|texel| texel == BASE.to_u8_array().as_slice())
+        );
+
+        // The center of the +Z face.
+        app.world_mut().spawn(PaintSplat {
+            surface,
+            local_position: Vec3::new(0.0, 0.0, 1.5),
+            local_normal: Vec3::Z,
+            color: Color::srgb(1.0, 0.0, 0.0),
+            radius: 0.5,
+            blend_mode: PaintBlendMode::Over,
+        });
+        app.update();
+
+        let uploads = app.world().resource::<CanvasUploads>();
+        assert_eq!(uploads.0.len(), 1);
+        let upload = &uploads.0[0];
+        assert_eq!(upload.image, texture.id());
+        assert!(upload.rect.contains(UVec2::new(24, 24)));
+        assert!(upload.rect.width() < 16 && upload.rect.height() < 16);
+        assert_eq!(texel(upload, UVec2::new(24, 24)), [255, 0, 0, 255]);
+        // The corners of the region are outside of the disc.
+        assert_eq!(texel(upload, upload.rect.min), BASE.to_u8_array());
+
+        // Nothing changed, so nothing is uploaded again.
+        app.update();
+        assert!(app.world().resource::<CanvasUploads>().0.is_empty());
+    }
+}
@@ -0,0 +1,420 @@
+//! Paint coverage scoring for surfaces with a [`PaintCanvas`].
+//!
+//! Coverage is measured in surface area rather than texels, so faces with a different texel
+//! density are weighted fairly. Only the region of a canvas that changed is measured again, and no
+//! rendering is needed, so it can be checked headlessly after a scripted spray run.
+
+use bevy::{math::URect, platform::collections::HashMap, prelude::*};
+
+use super::{
+    SpraySystems,
+    canvas::{PaintCanvas, stamp_splats},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<CoverageThresholdCrossed>().add_systems(
+        Update,
+        (
+            update_paint_coverage,
+            check_coverage_thresholds,
+            log_coverage_thresholds,
+        )
+            .chain()
+            .in_set(SpraySystems::Apply)
+            .after(stamp_splats),
+    );
+}
+
+/// Texels with less paint alpha than this don't count as covered.
+pub const COVERED_ALPHA: f32 = 0.5;
+
+/// Painted area of a single face, split by color.
+#[derive(Clone, Debug, Default)]
+pub struct FaceCoverage {
+    /// Total surface area of the face.
+    pub area: f32,
+    /// Covered area for every distinct paint color on the face.
+    pub by_color: Vec<(Srgba, f32)>,
+    /// The patch of the face every texel of its cell stands for.
+    texel_area: f32,
+    /// Covered texels for every distinct paint color on the face.
+    counts: HashMap<PaintKey, u32>,
+}
+
+impl FaceCoverage {
+    /// Measures the surface area of `face`, with nothing painted yet.
+    fn new(canvas: &PaintCanvas, face: usize) -> Self {
+        let texels = canvas.face_texels(face);
+        if texels.is_empty() {
+            return Self::default();
+        }
+
+        // Every texel of the cell stands for the same patch of the face.
+        let extent = canvas.layout().face_extent(face);
+        let texel_count = texels.size();
+        let texel_area = extent.x * extent.y / (texel_count.x * texel_count.y) as f32;
+        let surface_texels = (texels.min.y..texels.max.y)
+            .flat_map(|y| (texels.min.x..texels.max.x).map(move |x| UVec2::new(x, y)))
+            .filter(|texel| on_surface(canvas, face, *texel))
+            .count();
+        Self {
+            area: surface_texels as f32 * texel_area,
+            texel_area,
+            ..default()
+        }
+    }
+
+    fn update_by_color(&mut self) {
+        let mut by_color: Vec<(PaintKey, u32)> = self
+            .counts
+            .iter()
+            .map(|(key, count)| (*key, *count))
+            .collect();
+        by_color.sort_unstable_by_key(|(key, _)| *key);
+        self.by_color = by_color
+            .into_iter()
+            .map(|([red, green, blue], count)| {
+                (
+                    Srgba::rgb_u8(red, green, blue),
+                    count as f32 * self.texel_area,
+                )
+            })
+            .collect();
+    }
+
+    fn covered_area(&self, color: Option<Srgba>) -> f32 {
+        self.by_color
+            .iter()
+            .filter(|(painted, _)| color.is_none_or(|color| same_paint(*painted, color)))
+            .map(|(_, area)| area)
+            .sum()
+    }
+}
+
+/// How much of a paintable surface is covered, per face and per color.
+///
+/// Added automatically alongside every [`PaintCanvas`].
+#[derive(Component, Clone, Debug, Default)]
+pub struct PaintCoverage {
+    pub faces: Vec<FaceCoverage>,
+    /// The paint every texel of the canvas is covered by, if any, row by row.
+    texels: Vec<Option<PaintKey>>,
+}
+
+impl PaintCoverage {
+    /// Measures the texels of `rect` again. A canvas this coverage wasn't measured for yet is
+    /// measured as a whole.
+    fn update(&mut self, canvas: &PaintCanvas, rect: URect) {
+        let layout = canvas.layout();
+        let size = canvas.size();
+        let whole = URect::from_corners(UVec2::ZERO, size);
+        let rect = if self.texels.len() != (size.x * size.y) as usize
+            || self.faces.len() != layout.face_count()
+        {
+            self.texels = vec![None; (size.x * size.y) as usize];
+            self.faces = (0..layout.face_count())
+                .map(|face| FaceCoverage::new(canvas, face))
+                .collect();
+            whole
+        } else {
+            rect.intersect(whole)
+        };
+
+        for (face, coverage) in self.faces.iter_mut().enumerate() {
+            let texels = canvas.face_texels(face).intersect(rect);
+            if texels.is_empty() {
+                continue;
+            }
+
+            for y in texels.min.y..texels.max.y {
+                for x in texels.min.x..texels.max.x {
+                    let texel = UVec2::new(x, y);
+                    if !on_surface(canvas, face, texel) {
+                        continue;
+                    }
+                    let index = (y * size.x + x) as usize;
+                    let key = paint_key(canvas.pixels()[index]);
+                    let previous = std::mem::replace(&mut self.texels[index], key);
+                    if previous == key {
+                        continue;
+                    }
+                    if let Some(previous) = previous
+                        && let Some(count) = coverage.counts.get_mut(&previous)
+                    {
+                        *count -= 1;
+                        if *count == 0 {
+                            coverage.counts.remove(&previous);
+                        }
+                    }
+                    if let Some(key) = key {
+                        *coverage.counts.entry(key).or_default() += 1;
+                    }
+                }
+            }
+            coverage.update_by_color();
+        }
+    }
+
+    /// Fraction of the whole surface covered by `color`, or by any paint if `color` is `None`.
+    pub fn fraction(&self, color: Option<Color>) -> f32 {
+        self.fraction_on_faces(color, 0..self.faces.len())
+    }
+
+    /// Like [`PaintCoverage::fraction`], restricted to some faces of the
+    /// [`PaintUvLayout`](super::canvas::PaintUvLayout).
+    pub fn fraction_on_faces(
+        &self,
+        color: Option<Color>,
+        faces: impl IntoIterator<Item = usize>,
+    ) -> f32 {
+        let color = color.map(|color| color.to_srgba());
+        let (covered, area) = faces
+            .into_iter()
+            .filter_map(|face| self.faces.get(face))
+            .fold((0.0, 0.0), |(covered, area), face| {
+                (covered + face.covered_area(color), area + face.area)
+            });
+        if area > 0.0 { covered / area } else { 0.0 }
+    }
+
+    /// Every paint color on the surface with the fraction of the surface it covers.
+    pub fn colors(&self) -> Vec<(Srgba, f32)> {
+        let area: f32 = self.faces.iter().map(|face| face.area).sum();
+        let mut colors: Vec<(Srgba, f32)> = Vec::new();
+        for (color, covered) in self.faces.iter().flat_map(|face| &face.by_color) {
+            match colors.iter_mut().find(|(known, _)| same_paint(*known, *color)) {
+                Some((_, total)) => *total += covered,
+                None => colors.push((*color, *covered)),
+            }
+        }
+        if area > 0.0 {
+            colors.iter_mut().for_each(|(_, covered)| *covered /= area);
+        }
+        colors
+    }
+}
+
+/// Paint colors are compared at 8 bit sRGB precision, ignoring alpha.
+type PaintKey = [u8; 3];
+
+/// The paint covering a texel of a canvas, if its alpha is at least [`COVERED_ALPHA`].
+fn paint_key([red, green, blue, alpha]: [u8; 4]) -> Option<PaintKey> {
+    (alpha as f32 / 255.0 >= COVERED_ALPHA).then_some([red, green, blue])
+}
+
+fn same_paint(a: Srgba, b: Srgba) -> bool {
+    let a = a.to_u8_array();
+    let b = b.to_u8_array();
+    a[..3] == b[..3]
+}
+
+/// Whether the center of a texel in the cell of `face` lies on the surface.
+fn on_surface(canvas: &PaintCanvas, face: usize, texel: UVec2) -> bool {
+    let layout = canvas.layout();
+    let cell = layout.face_cell(face);
+    let canvas_uv = (texel.as_vec2() + 0.5) / canvas.size().as_vec2();
+    layout.face_contains(face, (canvas_uv - cell.min) / cell.size())
+}
+
+/// A coverage goal on a surface that sends [`CoverageThresholdCrossed`] when it is reached or lost.
+#[derive(Clone, Debug)]
+pub struct CoverageThreshold {
+    /// The color that has to be used, or any paint if `None`.
+    pub color: Option<Color>,
+    /// The fraction of the surface area that has to be covered, in `0..=1`.
+    pub fraction: f32,
+    /// Only these faces count towards the goal. All faces count if `None`.
+    pub faces: Option<Vec<usize>>,
+    reached: bool,
+}
+
+impl CoverageThreshold {
+    pub fn new(fraction: f32) -> Self {
+        Self {
+            color: None,
+            fraction,
+            faces: None,
+            reached: false,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn on_faces(mut self, faces: impl IntoIterator<Item = usize>) -> Self {
+        self.faces = Some(faces.into_iter().collect());
+        self
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.reached
+    }
+
+    fn coverage(&self, coverage: &PaintCoverage) -> f32 {
+        match &self.faces {
+            Some(faces) => coverage.fraction_on_faces(self.color, faces.iter().copied()),
+            None => coverage.fraction(self.color),
+        }
+    }
+}
+
+/// The coverage goals of a paintable surface.
+#[derive(Component, Clone, Debug, Default)]
+pub struct CoverageThresholds(pub Vec<CoverageThreshold>);
+
+/// Sent when the coverage of a surface rises to or falls below one of its [`CoverageThresholds`].
+#[derive(Event, Clone, Debug)]
+pub struct CoverageThresholdCrossed {
+    pub surface: Entity,
+    /// Index of the threshold in [`CoverageThresholds`].
+    pub index: usize,
+    pub color: Option<Color>,
+    pub threshold: f32,
+    pub coverage: f32,
+    /// True when the threshold was reached, false when paint was removed below it again.
+    pub reached: bool,
+}
+
+/// Measures the region of every canvas that changed since it was last measured.
+fn update_paint_coverage(
+    mut canvases: Query<(&mut PaintCanvas, &mut PaintCoverage), Changed<PaintCanvas>>,
+) {
+    for (mut canvas, mut coverage) in &mut canvases {
+        let Some(rect) = canvas.bypass_change_detection().take_unmeasured() else {
+            continue;
+        };
+        coverage.update(&canvas, rect);
+    }
+}
+
+/// Computes the [`PaintCoverage`] of a whole canvas.
+pub fn measure_coverage(canvas: &PaintCanvas) -> PaintCoverage {
+    let mut coverage = PaintCoverage::default();
+    coverage.update(canvas, URect::from_corners(UVec2::ZERO, canvas.size()));
+    coverage
+}
+
+/// Sends [`CoverageThresholdCrossed`] for every goal whose state changed.
+fn check_coverage_thresholds(
+    mut crossed: EventWriter<CoverageThresholdCrossed>,
+    mut surfaces: Query<(Entity, &PaintCoverage, &mut CoverageThresholds), Changed<PaintCoverage>>,
+) {
+    for (surface, coverage, mut thresholds) in &mut surfaces {
+        for (index, threshold) in thresholds.0.iter_mut().enumerate() {
+            let value = threshold.coverage(coverage);
+            let reached = value >= threshold.fraction;
+            if reached == threshold.reached {
+                continue;
+            }
+            threshold.reached = reached;
+            crossed.write(CoverageThresholdCrossed {
+                surface,
+                index,
+                color: threshold.color,
+                threshold: threshold.fraction,
+                coverage: value,
+                reached,
+            });
+        }
+    }
+}
+
+fn log_coverage_thresholds(mut crossed: EventReader<CoverageThresholdCrossed>) {
+    for event in crossed.read() {
+        info!(
+            "Surface {} {} {:.0}% coverage ({:.1}%)",
+            event.surface,
+            if event.reached { "reached" } else { "dropped below" },
+            event.threshold * 100.0,
+            event.coverage * 100.0,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spray::canvas::{PaintBlendMode, PaintUvLayout};
+    use crate::spray::splat::PaintSplat;
+
+    const RED: Color = Color::srgb(1.0, 0.0, 0.0);
+    const BLUE: Color = Color::srgb(0.0, 0.0, 1.0);
+
+    /// Sprays rows of overlapping splats over the +Z face of a 2x2x2 cuboid, one row per frame.
+    fn spray_rows(app: &mut App, surface: Entity, color: Color, rows: impl Iterator<Item = f32>) {
+        for y in rows {
+            for x in (0..=8).map(|step| -1.0 + step as f32 * 0.25) {
+                app.world_mut().spawn(PaintSplat {
+                    surface,
+                    local_position: Vec3::new(x, y, 1.0),
+                    local_normal: Vec3::Z,
+                    color,
+                    radius: 0.25,
+                    blend_mode: PaintBlendMode::Over,
+                });
+            }
+            app.update();
+        }
+    }
+
+    #[test]
+    fn scripted_spray_reaches_coverage_threshold() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, crate::spray::canvas::plugin, plugin));
+        let front = PaintUvLayout::CUBOID_FRONT;
+        let surface = app
+            .world_mut()
+            .spawn((
+                PaintCanvas::new(
+                    PaintUvLayout::Cuboid {
+                        size: Vec3::splat(2.0),
+                    },
+                    UVec2::new(48, 32),
+                ),
+                CoverageThresholds(vec![
+                    CoverageThreshold::new(0.9)
+                        .with_color(RED)
+                        .on_faces([front]),
+                ]),
+            ))
+            .id();
+        app.update();
+
+        let coverage = app.world().get::<PaintCoverage>(surface).unwrap();
+        assert!((coverage.faces.iter().map(|face| face.area).sum::<f32>() - 24.0).abs() < 1e-3);
+        assert_eq!(coverage.fraction(None), 0.0);
+
+        // Half of the face isn't enough.
+        let rows = |range: std::ops::RangeInclusive<i32>| range.map(|row| row as f32 * 0.25);
+        spray_rows(&mut app, surface, RED, rows(-4..=0));
+        let coverage = app.world().get::<PaintCoverage>(surface).unwrap();
+        let half = coverage.fraction_on_faces(Some(RED), [front]);
+        assert!((0.5..0.7).contains(&half), "{half}");
+        assert!(!app.world().get::<CoverageThresholds>(surface).unwrap().0[0].is_reached());
+
+        spray_rows(&mut app, surface, RED, rows(1..=4));
+        let coverage = app.world().get::<PaintCoverage>(surface).unwrap();
+        assert!(coverage.fraction_on_faces(Some(RED), [front]) > 0.99);
+        assert!((coverage.fraction(None) - 1.0 / 6.0).abs() < 1e-3);
+        assert!(app.world().get::<CoverageThresholds>(surface).unwrap().0[0].is_reached());
+
+        // Painting over the top row with another color takes it away from red.
+        spray_rows(&mut app, surface, BLUE, rows(4..=4));
+        let thresholds = app.world().get::<CoverageThresholds>(surface).unwrap();
+        assert!(!thresholds.0[0].is_reached());
+        let coverage = app.world().get::<PaintCoverage>(surface).unwrap();
+        let red = coverage.fraction_on_faces(Some(RED), [front]);
+        let blue = coverage.fraction_on_faces(Some(BLUE), [front]);
+        assert!(blue > 0.0 && (red + blue - 1.0).abs() < 1e-3);
+
+        // Measuring incrementally gives the same result as measuring the whole canvas.
+        let canvas = app.world().get::<PaintCanvas>(surface).unwrap();
+        let measured = measure_coverage(canvas);
+        for (face, expected) in coverage.faces.iter().zip(&measured.faces) {
+            assert_eq!(face.area, expected.area);
+            assert_eq!(face.by_color, expected.by_color);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+//! Drips of surplus paint that run down surfaces along gravity.
+
+use avian3d::{math::*, prelude::*};
+use bevy::prelude::*;
+
+use super::{
+    SpraySystems,
+    can::spray_paint,
+    canvas::{PaintBlendMode, PaintCanvas},
+    splat::PaintSplat,
+};
+use crate::character_controller::{GravityField, field_gravity_at};
+use crate::time::ClockTime;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        run_drips.in_set(SpraySystems::Spray).after(spray_paint),
+    );
+}
+
+/// The gravitational acceleration that pulls drips down a surface outside of [`GravityField`]s.
+///
+/// On a [`SprayCan`](super::can::SprayCan) it is copied to every drip the can produces.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DripGravity(pub Vec3);
+
+impl Default for DripGravity {
+    fn default() -> Self {
+        Self(Vec3::NEG_Y * 9.81)
+    }
+}
+
+/// A bead of paint running over a surface, leaving a trail of splats behind.
+///
+/// Drips stay on the face they landed on, and run off the surface at its edge. Runs on the
+/// [`OnClock`](crate::time::OnClock) clock of the can that sprayed it, if any.
+#[derive(Component, Clone, Debug)]
+#[require(DripGravity)]
+pub struct PaintDrip {
+    pub surface: Entity,
+    pub local_position: Vec3,
+    pub local_normal: Vec3,
+    pub color: Color,
+    /// Paint left in the drip, in millilitres.
+    pub volume: f32,
+    /// Distance run since the last trail splat.
+    travelled: f32,
+}
+
+impl PaintDrip {
+    /// Starts a drip where `splat` landed.
+    pub fn new(splat: &PaintSplat, color: Color, volume: f32) -> Self {
+        Self {
+            surface: splat.surface,
+            local_position: splat.local_position,
+            local_normal: splat.local_normal,
+            color,
+            volume,
+            travelled: 0.0,
+        }
+    }
+}
+
+/// Speed of a drip on a vertical surface, in meters per second.
+const DRIP_SPEED: f32 = 0.2;
+/// Below this fraction of gravity along the surface, drips stop and pool.
+const MIN_DRIP_SLOPE: f32 = 0.2;
+const TRAIL_SPACING: f32 = 0.02;
+const TRAIL_RADIUS: f32 = 0.015;
+/// Paint left behind by one trail splat, in millilitres.
+const TRAIL_VOLUME: f32 = 0.05;
+
+/// Moves drips downhill and lays down their trails.
+fn run_drips(
+    mut commands: Commands,
+    clock: ClockTime,
+    spatial_query: SpatialQuery,
+    fields: Query<(&GravityField, &Position, &Rotation)>,
+    mut drips: Query<(Entity, &mut PaintDrip, &DripGravity)>,
+    surfaces: Query<(
+        &GlobalTransform,
+        Option<&PaintCanvas>,
+        Option<&ColliderAabb>,
+    )>,
+) {
+    for (entity, mut drip, gravity) in &mut drips {
+        let Ok((surface_transform, canvas, aabb)) = surfaces.get(drip.surface) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        // Only the part of gravity along the surface moves the drip.
+        let position = surface_transform.transform_point(drip.local_position);
+        let gravity = field_gravity_at(&spatial_query, &fields, position.adjust_precision())
+            .map_or(gravity.0, |gravity| gravity.f32());
+        let local_gravity = surface_transform.rotation().inverse() * gravity;
+        let downhill = local_gravity.reject_from_normalized(drip.local_normal);
+        let slope = downhill.length() / local_gravity.length().max(f32::EPSILON);
+
+        if slope < MIN_DRIP_SLOPE {
+            // Too flat to run, so the rest of the paint pools where it is.
+            let radius = TRAIL_RADIUS * (1.0 + drip.volume / TRAIL_VOLUME).sqrt();
+            commands.spawn(drip_splat(&drip, drip.local_position, radius).into_bundle());
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let step = downhill.normalize() * DRIP_SPEED * slope * clock.delta_secs(entity);
+        let local_position = drip.local_position + step;
+
+        // Canvases know the bounds of their faces, other surfaces only their bounding box.
+        let on_surface = match (canvas, aabb) {
+            (Some(canvas), _) => canvas.layout().on_face(local_position, drip.local_normal),
+            (None, Some(aabb)) => {
+                let position = surface_transform.transform_point(local_position);
+                position.cmpge(aabb.min).all() && position.cmple(aabb.max).all()
+            }
+            (None, None) => true,
+        };
+        if !on_surface {
+            // The rest of the paint runs off the edge.
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        // A long step lays down its splats along the way rather than in one spot.
+        let length = step.length();
+        let mut along = TRAIL_SPACING - drip.travelled;
+        while along <= length && drip.volume > 0.0 {
+            drip.volume -= TRAIL_VOLUME;
+            let position = drip.local_position + step * (along / length);
+            commands.spawn(drip_splat(&drip, position, TRAIL_RADIUS).into_bundle());
+            along += TRAIL_SPACING;
+        }
+        drip.travelled = length - (along - TRAIL_SPACING);
+        drip.local_position = local_position;
+
+        if drip.volume <= 0.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn drip_splat(drip: &PaintDrip, local_position: Vec3, radius: f32) -> PaintSplat {
+    PaintSplat {
+        surface: drip.surface,
+        local_position,
+        local_normal: drip.local_normal,
+        color: drip.color,
+        radius,
+        blend_mode: PaintBlendMode::Over,
+    }
+}
@@ -0,0 +1,37 @@
+use bevy::prelude::*;
+
+pub mod can;
+pub mod canvas;
+pub mod coverage;
+pub mod drip;
+pub mod splat;
+
+/// The order paint moves through the spray systems within `Update`.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpraySystems {
+    /// Turns player input into [`can::SprayAction`] events.
+    Input,
+    /// Sprays paint and runs drips, spawning new [`splat::PaintSplat`]s.
+    Spray,
+    /// Stamps splats into canvases and measures coverage.
+    Apply,
+}
+
+pub fn add_all_plugins(app: &mut App) {
+    app.configure_sets(
+        Update,
+        (SpraySystems::Input, SpraySystems::Spray, SpraySystems::Apply).chain(),
+    );
+    app.add_plugins(can::plugin);
+    app.add_plugins(drip::plugin);
+    app.add_plugins(splat::plugin);
+    app.add_plugins(canvas::plugin);
+    app.add_plugins(coverage::plugin);
+}
+
+pub struct SprayPlugin;
+impl Plugin for SprayPlugin {
+    fn build(&self, app: &mut App) {
+        add_all_plugins(app);
+    }
+}
@@ -0,0 +1,102 @@
+//! Paint splats left behind by the spray can.
+//!
+//! Every splat is its own entity parented to the collider that was hit, so paint coverage can be
+//! queried from the ECS alone. Surfaces with a [`PaintCanvas`](super::canvas::PaintCanvas) absorb
+//! their splats instead. Meshes are only attached when the render assets exist.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use super::{SpraySystems, canvas::PaintBlendMode};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        attach_splat_visuals
+            .in_set(SpraySystems::Apply)
+            .run_if(resource_exists::<Assets<StandardMaterial>>),
+    );
+}
+
+/// A blob of paint stuck to a surface.
+///
+/// Position and normal are stored in the local space of `surface`, so the splat follows the
+/// surface when it moves.
+#[derive(Component, Clone, Debug)]
+pub struct PaintSplat {
+    /// The collider entity the paint landed on.
+    pub surface: Entity,
+    pub local_position: Vec3,
+    pub local_normal: Vec3,
+    pub color: Color,
+    pub radius: f32,
+    /// How the splat combines with paint already on a [`PaintCanvas`](super::canvas::PaintCanvas).
+    pub blend_mode: PaintBlendMode,
+}
+
+/// Offset along the normal used by splat meshes to avoid z-fighting with the surface.
+const SPLAT_SURFACE_OFFSET: f32 = 0.002;
+
+impl PaintSplat {
+    /// Creates a splat from a world-space hit on `surface`.
+    pub fn from_world_hit(
+        surface: Entity,
+        surface_transform: &GlobalTransform,
+        point: Vec3,
+        normal: Vec3,
+        color: Color,
+        radius: f32,
+        blend_mode: PaintBlendMode,
+    ) -> Self {
+        Self {
+            surface,
+            local_position: surface_transform.affine().inverse().transform_point3(point),
+            local_normal: (surface_transform.rotation().inverse() * normal).normalize_or_zero(),
+            color,
+            radius,
+            blend_mode,
+        }
+    }
+
+    /// The splat together with its transform, parented to its surface.
+    pub fn into_bundle(self) -> impl Bundle {
+        // Oriented and scaled so a unit disc facing +Z covers the splat.
+        let transform =
+            Transform::from_translation(self.local_position + self.local_normal * SPLAT_SURFACE_OFFSET)
+                .with_rotation(Quat::from_rotation_arc(Vec3::Z, self.local_normal))
+                .with_scale(Vec3::splat(self.radius));
+        let parent = ChildOf(self.surface);
+        (self, transform, parent)
+    }
+}
+
+/// Gives newly spawned splats a flat disc mesh so they show up on screen.
+pub(super) fn attach_splat_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    splats: Query<(Entity, &PaintSplat), Added<PaintSplat>>,
+    mut disc: Local<Option<Handle<Mesh>>>,
+    mut color_materials: Local<HashMap<[u8; 4], Handle<StandardMaterial>>>,
+) {
+    for (entity, splat) in &splats {
+        let mesh = disc
+            .get_or_insert_with(|| meshes.add(Circle::new(1.0)))
+            .clone();
+        let material = color_materials
+            .entry(splat.color.to_srgba().to_u8_array())
+            .or_insert_with(|| {
+                materials.add(StandardMaterial {
+                    base_color: splat.color,
+                    alpha_mode: AlphaMode::Blend,
+                    ..default()
+                })
+            })
+            .clone();
+
+        commands
+            .entity(entity)
+            .insert((Mesh3d(mesh), MeshMaterial3d(material)));
+    }
+}
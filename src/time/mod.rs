@@ -0,0 +1,515 @@
+//! Virtual clocks that run at their own speed, and can be paused, on top of the game's time.
+//!
+//! Every [`ClockDomain`] has a [`VirtualClock`] entity, listed in [`Clocks`]. Clocks can run
+//! within a parent clock, so slowing down or pausing the scenario clock slows down or pauses the
+//! life and physics clocks with it, while the UI clock keeps running on real time.
+//!
+//! Entities opt into a clock with [`OnClock`], and systems read their time step through
+//! [`ClockTime`] instead of [`Time`]. Entities without a clock get the plain [`Time`] step. Whole
+//! systems can be run on a clock with the [`clock_running`] run condition. The physics clock also
+//! sets the speed of the physics simulation.
+//!
+//! Based on the `VirtualClock` of `examples/virtual_time.rs`.
+
+use avian3d::prelude::*;
+use bevy::{
+    ecs::system::SystemParam, platform::collections::HashMap, prelude::*, time::Stopwatch,
+    time::TimeSystem,
+};
+
+/// How deep clocks can be nested. Deeper clocks, like ones in a cycle, are stopped.
+const MAX_CLOCK_DEPTH: usize = 16;
+
+pub fn add_all_plugins(app: &mut App) {
+    let scenario = app
+        .world_mut()
+        .spawn((
+            Name::new("Scenario clock"),
+            ClockDomain::Scenario,
+            VirtualClock::new(),
+        ))
+        .id();
+    let life = app
+        .world_mut()
+        .spawn((
+            Name::new("Life clock"),
+            ClockDomain::Life,
+            VirtualClock::new().within(scenario),
+        ))
+        .id();
+    let physics = app
+        .world_mut()
+        .spawn((
+            Name::new("Physics clock"),
+            ClockDomain::Physics,
+            VirtualClock::new().within(scenario),
+        ))
+        .id();
+    let ui = app
+        .world_mut()
+        .spawn((
+            Name::new("UI clock"),
+            ClockDomain::Ui,
+            VirtualClock::real_time(),
+        ))
+        .id();
+
+    app.insert_resource(Clocks(HashMap::from_iter([
+        (ClockDomain::Scenario, scenario),
+        (ClockDomain::Life, life),
+        (ClockDomain::Physics, physics),
+        (ClockDomain::Ui, ui),
+    ])))
+    .add_systems(
+        First,
+        (update_clocks, sync_physics_time)
+            .chain()
+            .in_set(ClockSystems)
+            .after(TimeSystem),
+    );
+}
+
+pub struct ClockPlugin;
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut App) {
+        add_all_plugins(app);
+    }
+}
+
+/// Ticks every [`VirtualClock`] in `First`, right after the game's time is updated.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClockSystems;
+
+/// The clocks the game has one of, each for a part of the game that runs at its own speed.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClockDomain {
+    /// The game as a whole, and the clock the life and physics clocks run within.
+    Scenario,
+    /// Things that live in the world, like AI.
+    Life,
+    /// Menus and other UI, which keep running on real time when the game is paused.
+    Ui,
+    /// The physics simulation, and the character controller and paint drips running with it.
+    Physics,
+}
+
+/// The [`VirtualClock`] entity of each [`ClockDomain`].
+#[derive(Resource, Clone, Debug)]
+pub struct Clocks(HashMap<ClockDomain, Entity>);
+
+impl Clocks {
+    pub fn get(&self, domain: ClockDomain) -> Entity {
+        self.0[&domain]
+    }
+}
+
+/// A clock with its own speed, that can be paused, within the clock of its `parent`.
+#[derive(Component, Clone, Debug)]
+pub struct VirtualClock {
+    stopwatch: Stopwatch,
+    /// How fast this clock runs relative to its parent.
+    pub speed: f32,
+    pub paused: bool,
+    /// The clock this one runs within. Its speed and pause apply to this clock too.
+    pub parent: Option<Entity>,
+    /// Runs on [`Time<Real>`] instead of [`Time<Virtual>`], if it has no parent.
+    real_time: bool,
+    /// The speed relative to the game's time, with every parent taken into account.
+    relative_speed: f32,
+    /// Whether the outermost parent runs on real time.
+    runs_on_real_time: bool,
+    delta_secs: f32,
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self {
+            stopwatch: Stopwatch::new(),
+            speed: 1.0,
+            paused: false,
+            parent: None,
+            real_time: false,
+            relative_speed: 1.0,
+            runs_on_real_time: false,
+            delta_secs: 0.0,
+        }
+    }
+
+    /// A clock that keeps running when the game is paused.
+    pub fn real_time() -> Self {
+        Self {
+            real_time: true,
+            ..Self::new()
+        }
+    }
+
+    /// Runs this clock within `parent`.
+    pub fn within(mut self, parent: Entity) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Time passed on this clock since it started.
+    pub fn elapsed_secs(&self) -> f32 {
+        self.stopwatch.elapsed_secs()
+    }
+
+    /// Time passed on this clock during this frame.
+    pub fn delta_secs(&self) -> f32 {
+        self.delta_secs
+    }
+
+    /// How fast this clock runs relative to the game's time, or real time for real time clocks,
+    /// with its parents taken into account. Zero while it or a parent is paused.
+    pub fn relative_speed(&self) -> f32 {
+        self.relative_speed
+    }
+
+    /// Whether this clock keeps running when the game is paused, through its outermost parent.
+    pub fn runs_on_real_time(&self) -> bool {
+        self.runs_on_real_time
+    }
+}
+
+/// Makes an entity opt into the time step of a [`VirtualClock`] entity, for systems that read
+/// it through [`ClockTime`].
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OnClock(pub Entity);
+
+/// The time step of entities, according to the [`VirtualClock`] they are [`OnClock`].
+///
+/// Steps are scaled from the [`Time`] of the schedule the system runs in, so this works the same
+/// in `Update` and in `FixedUpdate`. Real time clocks get the real time that passed for that step,
+/// or the real frame time while the game is paused. The [`Time`] of the [`PhysicsSchedule`] already runs at the
+/// speed of the [`ClockDomain::Physics`] clock, so systems there use
+/// [`ClockTime::physics_delta_secs_f64`] instead.
+#[derive(SystemParam)]
+pub struct ClockTime<'w, 's> {
+    time: Res<'w, Time>,
+    virtual_time: Res<'w, Time<Virtual>>,
+    real_time: Res<'w, Time<Real>>,
+    domains: Option<Res<'w, Clocks>>,
+    clocks: Query<'w, 's, &'static VirtualClock>,
+    on_clock: Query<'w, 's, &'static OnClock>,
+}
+
+impl ClockTime<'_, '_> {
+    /// The time step of `entity`, or of the schedule if it isn't on a clock.
+    pub fn delta_secs(&self, entity: Entity) -> f32 {
+        self.delta_secs_f64(entity) as f32
+    }
+
+    /// The time step of `entity`, or of the schedule if it isn't on a clock.
+    pub fn delta_secs_f64(&self, entity: Entity) -> f64 {
+        match self.entity_clock(entity) {
+            Some(clock) => self.clock_delta_secs_f64(clock),
+            None => self.time.delta_secs_f64(),
+        }
+    }
+
+    /// The time step of `entity` in the [`PhysicsSchedule`], or of the physics step if it isn't
+    /// on a clock. Only the speed of its clock relative to the physics clock is applied, as the
+    /// physics step is scaled by the physics clock already.
+    pub fn physics_delta_secs_f64(&self, entity: Entity) -> f64 {
+        let delta = self.time.delta_secs_f64();
+        let Some(clock) = self.entity_clock(entity) else {
+            return delta;
+        };
+        let physics_speed = self
+            .domains
+            .as_ref()
+            .and_then(|domains| self.clocks.get(domains.get(ClockDomain::Physics)).ok())
+            .map_or(1.0, |physics| physics.relative_speed);
+        if physics_speed <= 0.0 {
+            return 0.0;
+        }
+        delta * (clock.relative_speed / physics_speed) as f64
+    }
+
+    fn entity_clock(&self, entity: Entity) -> Option<&VirtualClock> {
+        let on_clock = self.on_clock.get(entity).ok()?;
+        self.clocks.get(on_clock.0).ok()
+    }
+
+    /// The time step of the clock of `domain`, or of the schedule if the game has no clocks.
+    pub fn domain_delta_secs(&self, domain: ClockDomain) -> f32 {
+        match &self.domains {
+            Some(domains) => self.clock_delta_secs(domains.get(domain)),
+            None => self.time.delta_secs(),
+        }
+    }
+
+    /// The time step of the `clock` entity itself.
+    pub fn clock_delta_secs(&self, clock: Entity) -> f32 {
+        self.clocks
+            .get(clock)
+            .map_or(self.time.delta_secs(), |clock| {
+                self.clock_delta_secs_f64(clock) as f32
+            })
+    }
+
+    fn clock_delta_secs_f64(&self, clock: &VirtualClock) -> f64 {
+        let delta = if !clock.runs_on_real_time {
+            self.time.delta_secs_f64()
+        } else if self.virtual_time.delta_secs_f64() > 0.0 {
+            // The real time that passed per second of game time this frame.
+            let real_per_virtual =
+                self.real_time.delta_secs_f64() / self.virtual_time.delta_secs_f64();
+            self.time.delta_secs_f64() * real_per_virtual
+        } else {
+            self.real_time.delta_secs_f64()
+        };
+        delta * clock.relative_speed as f64
+    }
+}
+
+/// Runs a system only while the clock of `domain` is running.
+pub fn clock_running(
+    domain: ClockDomain,
+) -> impl FnMut(Res<Clocks>, Query<&'static VirtualClock>) -> bool {
+    move |clocks, query| {
+        query
+            .get(clocks.get(domain))
+            .is_ok_and(|clock| clock.relative_speed > 0.0)
+    }
+}
+
+/// Works out the speed of every clock within its parents, and advances it.
+fn update_clocks(
+    virtual_time: Res<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+    mut clocks: Query<(Entity, &mut VirtualClock)>,
+) {
+    let settings: HashMap<Entity, (f32, Option<Entity>, bool)> = clocks
+        .iter()
+        .map(|(entity, clock)| {
+            let speed = if clock.paused { 0.0 } else { clock.speed };
+            (entity, (speed, clock.parent, clock.real_time))
+        })
+        .collect();
+
+    for (entity, mut clock) in &mut clocks {
+        // Multiply the speeds up to the outermost clock, which decides what time it runs on.
+        let mut relative_speed = 1.0;
+        let mut real = false;
+        let mut next = Some(entity);
+        for _ in 0..MAX_CLOCK_DEPTH {
+            let Some((speed, parent, real_time)) = next.and_then(|next| settings.get(&next)) else {
+                break;
+            };
+            relative_speed *= speed;
+            real = *real_time;
+            next = *parent;
+        }
+        if next.is_some_and(|next| settings.contains_key(&next)) {
+            warn_once!("Clocks are nested too deep, or in a cycle");
+            relative_speed = 0.0;
+        }
+
+        let delta = if real {
+            real_time.delta()
+        } else {
+            virtual_time.delta()
+        };
+        let scaled = delta.mul_f32(relative_speed.max(0.0));
+        clock.stopwatch.tick(scaled);
+        clock.relative_speed = relative_speed;
+        clock.runs_on_real_time = real;
+        clock.delta_secs = scaled.as_secs_f32();
+    }
+}
+
+/// Runs the physics simulation at the speed of the [`ClockDomain::Physics`] clock.
+///
+/// Pausing physics on its own is left to [`Time<Physics>`] itself.
+fn sync_physics_time(
+    clocks: Res<Clocks>,
+    query: Query<&VirtualClock>,
+    physics_time: Option<ResMut<Time<Physics>>>,
+) {
+    let (Ok(clock), Some(mut physics_time)) =
+        (query.get(clocks.get(ClockDomain::Physics)), physics_time)
+    else {
+        return;
+    };
+    if physics_time.relative_speed() != clock.relative_speed {
+        physics_time.set_relative_speed(clock.relative_speed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    const FRAME: f32 = 1.0 / 60.0;
+
+    /// An app with the game's clocks, advancing one [`FRAME`] on every update.
+    fn app() -> App {
+        app_with(|_| {})
+    }
+
+    fn app_with(setup: impl FnOnce(&mut App)) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, ClockPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                FRAME,
+            )));
+        setup(&mut app);
+        // The first update only starts the game's time.
+        app.update();
+        app
+    }
+
+    fn clock(app: &mut App, domain: ClockDomain) -> Mut<'_, VirtualClock> {
+        let entity = app.world().resource::<Clocks>().get(domain);
+        app.world_mut().get_mut::<VirtualClock>(entity).unwrap()
+    }
+
+    fn delta(app: &mut App, domain: ClockDomain) -> f32 {
+        clock(app, domain).delta_secs()
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-5, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn pausing_the_scenario_pauses_life_and_physics_but_not_ui() {
+        let mut app = app();
+        clock(&mut app, ClockDomain::Scenario).toggle_pause();
+        let life_elapsed = clock(&mut app, ClockDomain::Life).elapsed_secs();
+        for _ in 0..5 {
+            app.update();
+        }
+
+        for domain in [
+            ClockDomain::Scenario,
+            ClockDomain::Life,
+            ClockDomain::Physics,
+        ] {
+            assert_eq!(delta(&mut app, domain), 0.0, "{domain:?}");
+        }
+        assert_eq!(
+            clock(&mut app, ClockDomain::Life).elapsed_secs(),
+            life_elapsed
+        );
+        assert_close(delta(&mut app, ClockDomain::Ui), FRAME);
+
+        clock(&mut app, ClockDomain::Scenario).toggle_pause();
+        app.update();
+        assert_close(delta(&mut app, ClockDomain::Life), FRAME);
+    }
+
+    #[test]
+    fn nested_speeds_multiply() {
+        let mut app = app();
+        clock(&mut app, ClockDomain::Scenario).speed = 0.5;
+        clock(&mut app, ClockDomain::Physics).speed = 0.5;
+        app.update();
+
+        assert_close(delta(&mut app, ClockDomain::Scenario), FRAME * 0.5);
+        assert_close(delta(&mut app, ClockDomain::Life), FRAME * 0.5);
+        assert_close(delta(&mut app, ClockDomain::Physics), FRAME * 0.25);
+        assert_eq!(clock(&mut app, ClockDomain::Physics).relative_speed(), 0.25);
+        assert_close(delta(&mut app, ClockDomain::Ui), FRAME);
+    }
+
+    #[test]
+    fn clocks_in_a_cycle_are_stopped() {
+        let mut app = app();
+        let first = app.world_mut().spawn(VirtualClock::new()).id();
+        let second = app
+            .world_mut()
+            .spawn(VirtualClock::new().within(first))
+            .id();
+        app.world_mut()
+            .get_mut::<VirtualClock>(first)
+            .unwrap()
+            .parent = Some(second);
+        app.update();
+
+        for entity in [first, second] {
+            let clock = app.world().get::<VirtualClock>(entity).unwrap();
+            assert_eq!(clock.relative_speed(), 0.0);
+            assert_eq!(clock.delta_secs(), 0.0);
+        }
+        // Clocks outside the cycle keep running.
+        assert_close(delta(&mut app, ClockDomain::Scenario), FRAME);
+    }
+
+    #[test]
+    fn physics_runs_at_the_physics_clock_speed() {
+        let mut app = app_with(|app| {
+            app.add_plugins((AssetPlugin::default(), PhysicsPlugins::default()))
+                .init_asset::<Mesh>();
+        });
+        clock(&mut app, ClockDomain::Physics).speed = 0.5;
+        app.update();
+        assert_eq!(
+            app.world().resource::<Time<Physics>>().relative_speed(),
+            0.5
+        );
+
+        clock(&mut app, ClockDomain::Scenario).toggle_pause();
+        app.update();
+        assert_eq!(
+            app.world().resource::<Time<Physics>>().relative_speed(),
+            0.0
+        );
+    }
+
+    #[derive(Resource, Default)]
+    struct UiTime(f64);
+
+    fn add_ui_time(clock_time: ClockTime, clocks: Res<Clocks>, mut ui_time: ResMut<UiTime>) {
+        ui_time.0 += clock_time.clock_delta_secs(clocks.get(ClockDomain::Ui)) as f64;
+    }
+
+    #[test]
+    fn real_time_clocks_keep_real_time_in_fixed_update() {
+        let mut app = app_with(|app| {
+            app.init_resource::<UiTime>()
+                .add_systems(FixedUpdate, add_ui_time);
+        });
+        app.world_mut()
+            .resource_mut::<Time<Virtual>>()
+            .set_relative_speed(0.5);
+        let start = app.world().resource::<Time<Real>>().elapsed_secs_f64();
+        for _ in 0..120 {
+            app.update();
+        }
+
+        // Up to the game time of one fixed step is left over in the accumulator at either end,
+        // which is two steps of real time at half speed.
+        let real_elapsed = app.world().resource::<Time<Real>>().elapsed_secs_f64() - start;
+        let fixed_step = app
+            .world()
+            .resource::<Time<Fixed>>()
+            .timestep()
+            .as_secs_f64();
+        let ui_time = app.world().resource::<UiTime>().0;
+        assert!(
+            (ui_time - real_elapsed).abs() < 2.0 * fixed_step,
+            "{ui_time} != {real_elapsed}"
+        );
+    }
+}